@@ -1,12 +1,14 @@
 use gettextrs::gettext;
-use log::{debug, info};
+use log::{debug, info, warn};
 
 use glib::clone;
 use gtk4::prelude::*;
 use gtk4::subclass::prelude::*;
 use gtk4::{gdk, gio, glib};
 
+use crate::components::ThemeEditor;
 use crate::config::{APP_ID, PKGDATADIR, PROFILE, VERSION};
+use crate::dbus;
 use crate::window::ThemeEditorApplicationWindow;
 
 mod imp {
@@ -41,6 +43,9 @@ mod imp {
 
             let provider = app.setup_css();
             let window = ThemeEditorApplicationWindow::new(app, provider);
+            if let Some(theme_editor) = window.theme_editor() {
+                dbus::connect_signals(app, &theme_editor);
+            }
             self.window
                 .set(window.downgrade())
                 .expect("Window already set.");
@@ -48,6 +53,44 @@ mod imp {
             app.main_window().present();
         }
 
+        fn command_line(
+            &self,
+            app: &Self::Type,
+            command_line: &gio::ApplicationCommandLine,
+        ) -> i32 {
+            debug!("GtkApplication<ThemeEditorApplication>::command_line");
+            // runs in the primary instance, with the arguments of whichever instance was launched
+            let mut files = Vec::new();
+            let mut options_ended = false;
+            for arg in command_line.arguments().iter().skip(1) {
+                let text = arg.to_string_lossy();
+                if !options_ended && text == "--" {
+                    options_ended = true;
+                } else if !options_ended && text.starts_with('-') {
+                    // the editor takes no options, only files to open
+                    command_line.printerr_literal(&format!("unknown option {}\n", text));
+                    return 1;
+                } else {
+                    files.push(command_line.create_file_for_arg(arg));
+                }
+            }
+            if files.is_empty() {
+                app.activate();
+            } else {
+                app.open(&files, "");
+            }
+            0
+        }
+
+        fn open(&self, app: &Self::Type, files: &[gio::File], _hint: &str) {
+            debug!("GtkApplication<ThemeEditorApplication>::open");
+            app.activate();
+
+            for f in files {
+                app.main_window().open_file(f);
+            }
+        }
+
         fn startup(&self, app: &Self::Type) {
             debug!("GtkApplication<ThemeEditorApplication>::startup");
             self.parent_startup(app);
@@ -57,6 +100,10 @@ mod imp {
 
             app.setup_gactions();
             app.setup_accels();
+
+            if let Err(e) = dbus::register(app) {
+                warn!("failed to export the D-Bus interface: {}", e);
+            }
         }
     }
 
@@ -73,7 +120,11 @@ impl ThemeEditorApplication {
     pub fn new() -> Self {
         glib::Object::new(&[
             ("application-id", &Some(APP_ID)),
-            ("flags", &gio::ApplicationFlags::empty()),
+            (
+                "flags",
+                &(gio::ApplicationFlags::HANDLES_OPEN
+                    | gio::ApplicationFlags::HANDLES_COMMAND_LINE),
+            ),
             (
                 "resource-base-path",
                 &Some("/com/System76/CosmicThemeEditor/"),
@@ -86,6 +137,14 @@ impl ThemeEditorApplication {
         self.imp().window.get().unwrap().upgrade().unwrap()
     }
 
+    pub fn theme_editor(&self) -> Option<ThemeEditor> {
+        self.imp()
+            .window
+            .get()
+            .and_then(|window| window.upgrade())
+            .and_then(|window| window.theme_editor())
+    }
+
     fn setup_gactions(&self) {
         // Quit
         let action_quit = gio::SimpleAction::new("quit", None);
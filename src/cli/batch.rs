@@ -0,0 +1,174 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::{
+    export::{preview_png, selection_from_swatches},
+    util::palette_from_path,
+};
+use clap::Args;
+use cosmic_theme::{ColorPicker, Derivation, Exact, ThemeConstraints};
+use rayon::prelude::*;
+use ron::ser::PrettyConfig;
+use std::{
+    collections::HashMap,
+    fs,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+};
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "bmp", "tif", "tiff"];
+/// Narrower previews have no room left for their surfaces
+const MIN_THUMBNAIL_WIDTH: i32 = 16;
+
+#[derive(Args)]
+pub struct BatchArgs {
+    /// Directory of wallpapers
+    dir: PathBuf,
+    /// Directory the themes and their previews are written to
+    #[clap(long)]
+    out: PathBuf,
+    /// Number of colors to extract from each image
+    #[clap(long, default_value = "5")]
+    clusters: NonZeroUsize,
+    /// Seed for the k-means clustering
+    #[clap(long, default_value_t = 0)]
+    seed: u64,
+    /// Lighten elevated surfaces
+    #[clap(long)]
+    lighten: bool,
+    /// Width of the preview thumbnails
+    #[clap(long, default_value_t = 256, parse(try_from_str = thumbnail_width))]
+    thumbnail_width: i32,
+}
+
+fn thumbnail_width(s: &str) -> Result<i32, String> {
+    let width: i32 = s.parse().map_err(|e| format!("{}", e))?;
+    if width < MIN_THUMBNAIL_WIDTH {
+        return Err(format!("must be at least {}", MIN_THUMBNAIL_WIDTH));
+    }
+    Ok(width)
+}
+
+enum Outcome {
+    Written,
+    /// the theme was written, but did not meet its constraints
+    DerivationErrors(Vec<String>),
+    Failed(anyhow::Error),
+}
+
+pub fn run(args: BatchArgs) -> anyhow::Result<i32> {
+    let images: Vec<PathBuf> = fs::read_dir(&args.dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .and_then(|extension| extension.to_str())
+                .map_or(false, |extension| {
+                    IMAGE_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+                })
+        })
+        .collect();
+    fs::create_dir_all(&args.out)?;
+
+    let constraints = ThemeConstraints {
+        lighten: args.lighten,
+        ..Default::default()
+    };
+
+    // images that share a file stem would write over each other's theme and preview
+    let mut by_name: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    for image in images {
+        by_name.entry(output_name(&image)).or_default().push(image);
+    }
+    let (unique, colliding): (Vec<_>, Vec<_>) =
+        by_name.into_values().partition(|images| images.len() == 1);
+
+    let mut outcomes: Vec<(PathBuf, Outcome)> = unique
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map(|image| {
+            let outcome = match theme_from_image(&image, &args, constraints) {
+                Ok(errors) if errors.is_empty() => Outcome::Written,
+                Ok(errors) => Outcome::DerivationErrors(errors),
+                Err(e) => Outcome::Failed(e),
+            };
+            (image, outcome)
+        })
+        .collect();
+    for images in colliding {
+        for image in &images {
+            let others: Vec<_> = images
+                .iter()
+                .filter(|other| *other != image)
+                .map(|other| other.display().to_string())
+                .collect();
+            let e = anyhow::anyhow!("skipped, {} has the same output name", others.join(", "));
+            outcomes.push((image.clone(), Outcome::Failed(e)));
+        }
+    }
+    outcomes.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut written = 0;
+    let mut problems = 0;
+    for (image, outcome) in &outcomes {
+        match outcome {
+            Outcome::Written => written += 1,
+            Outcome::DerivationErrors(errors) => {
+                written += 1;
+                problems += 1;
+                eprintln!("{}: derivation errors", image.display());
+                for e in errors {
+                    eprintln!("    {}", e);
+                }
+            }
+            Outcome::Failed(e) => {
+                problems += 1;
+                eprintln!("{}: {:#}", image.display(), e);
+            }
+        }
+    }
+    println!(
+        "{} of {} themes written to {}, {} with problems",
+        written,
+        outcomes.len(),
+        args.out.display(),
+        problems
+    );
+    Ok(if problems > 0 { 1 } else { 0 })
+}
+
+/// The theme and its preview are named after the image
+fn output_name(image: &Path) -> String {
+    image
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Write the theme for one image and its preview, returning any derivation errors
+fn theme_from_image(
+    image: &Path,
+    args: &BatchArgs,
+    constraints: ThemeConstraints,
+) -> anyhow::Result<Vec<String>> {
+    let name = output_name(image);
+    let palette = palette_from_path(image, args.clusters.get(), args.seed)?;
+    let selection = selection_from_swatches(palette.into_iter().map(|(c, _)| c).collect())?;
+
+    let Derivation {
+        derived: mut theme,
+        errors,
+    } = Exact::new(selection, constraints).theme_derivation();
+    theme.name = name.clone();
+
+    fs::write(
+        args.out.join(format!("{}.ron", name)),
+        ron::ser::to_string_pretty(&theme, PrettyConfig::new())?,
+    )?;
+    let width = args.thumbnail_width;
+    fs::write(
+        args.out.join(format!("{}.png", name)),
+        preview_png(&theme, width, width * 5 / 8)?,
+    )?;
+    Ok(errors.iter().map(|e| e.to_string()).collect())
+}
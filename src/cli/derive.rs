@@ -0,0 +1,119 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::{
+    export::exporters,
+    util::{rgba_from_hex, SRGBA},
+};
+use clap::{Args, PossibleValue};
+use cosmic_theme::{ColorPicker, Derivation, Exact, Selection, Theme, ThemeConstraints};
+use std::io::{self, Write};
+
+#[derive(Args)]
+pub struct DeriveArgs {
+    #[clap(long, parse(try_from_str = rgba_from_hex))]
+    background: SRGBA,
+    #[clap(long, parse(try_from_str = rgba_from_hex))]
+    primary_container: SRGBA,
+    #[clap(long, parse(try_from_str = rgba_from_hex))]
+    secondary_container: SRGBA,
+    #[clap(long, parse(try_from_str = rgba_from_hex))]
+    accent: SRGBA,
+    #[clap(long, parse(try_from_str = rgba_from_hex))]
+    accent_text: Option<SRGBA>,
+    #[clap(long, parse(try_from_str = rgba_from_hex))]
+    accent_nav_handle_text: Option<SRGBA>,
+    #[clap(long, parse(try_from_str = rgba_from_hex))]
+    destructive: SRGBA,
+    #[clap(flatten)]
+    output: ThemeOutput,
+}
+
+/// Constraints and output options shared by the subcommands that derive themes
+#[derive(Args)]
+pub struct ThemeOutput {
+    /// Lighten elevated surfaces
+    #[clap(long)]
+    lighten: bool,
+    /// Name of the theme
+    #[clap(long)]
+    name: Option<String>,
+    /// Format printed to stdout
+    #[clap(long, possible_values = formats(), default_value = "css")]
+    format: String,
+    /// Write the theme to the COSMIC theme directory instead of printing it
+    #[clap(long)]
+    write: bool,
+}
+
+/// The ids of the export formats, described by their names
+fn formats() -> Vec<PossibleValue<'static>> {
+    exporters(None)
+        .iter()
+        .map(|exporter| PossibleValue::new(exporter.id()).help(exporter.name()))
+        .collect()
+}
+
+impl ThemeOutput {
+    pub fn constraints(&self) -> ThemeConstraints {
+        ThemeConstraints {
+            lighten: self.lighten,
+            ..Default::default()
+        }
+    }
+
+    /// Derive a theme the same way as the Preview button, named `default_name` unless `--name` is given.
+    /// Derivation errors are printed, and give `None`.
+    pub fn derive(&self, selection: Selection<SRGBA>, default_name: &str) -> Option<Theme<SRGBA>> {
+        let Derivation {
+            derived: mut theme,
+            errors,
+        } = Exact::new(selection, self.constraints()).theme_derivation();
+        if !errors.is_empty() {
+            for e in errors {
+                eprintln!("{}", e);
+            }
+            return None;
+        }
+        theme.name = self
+            .name
+            .clone()
+            .unwrap_or_else(|| default_name.to_string());
+        Some(theme)
+    }
+
+    /// Print or write the theme
+    pub fn output(&self, theme: &Theme<SRGBA>, selection: &Selection<SRGBA>) -> anyhow::Result<()> {
+        if self.write {
+            theme.write().map_err(|e| anyhow::anyhow!("{}", e))?;
+            println!("wrote the {} theme", theme.name);
+            return Ok(());
+        }
+        // clap only accepts the ids of the exporters
+        let exporter = exporters(None)
+            .into_iter()
+            .find(|exporter| exporter.id() == self.format)
+            .unwrap();
+        io::stdout().write_all(&exporter.export(theme, selection)?)?;
+        Ok(())
+    }
+}
+
+pub fn run(args: DeriveArgs) -> anyhow::Result<i32> {
+    let selection = Selection {
+        background: args.background,
+        primary_container: args.primary_container,
+        secondary_container: args.secondary_container,
+        accent: args.accent,
+        accent_text: args.accent_text,
+        accent_nav_handle_text: args.accent_nav_handle_text,
+        destructive: args.destructive,
+    };
+
+    match args.output.derive(selection, "Custom") {
+        Some(theme) => {
+            args.output.output(&theme, &selection)?;
+            Ok(0)
+        }
+        None => Ok(1),
+    }
+}
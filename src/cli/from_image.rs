@@ -0,0 +1,68 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use super::derive::ThemeOutput;
+use crate::{export::selection_from_swatches, util::palette_from_path};
+use clap::Args;
+use serde::Serialize;
+use std::{num::NonZeroUsize, path::PathBuf};
+
+#[derive(Args)]
+pub struct FromImageArgs {
+    image: PathBuf,
+    /// Number of colors to extract
+    #[clap(long, default_value = "5")]
+    clusters: NonZeroUsize,
+    /// Seed for the k-means clustering
+    #[clap(long, default_value_t = 0)]
+    seed: u64,
+    /// Print the swatches as JSON
+    #[clap(long, conflicts_with = "derive")]
+    json: bool,
+    /// Assign the swatches to roles and derive a theme instead of printing them
+    #[clap(long)]
+    derive: bool,
+    #[clap(flatten)]
+    output: ThemeOutput,
+}
+
+#[derive(Serialize)]
+struct Swatch {
+    color: String,
+    percentage: f32,
+}
+
+pub fn run(args: FromImageArgs) -> anyhow::Result<i32> {
+    let palette = palette_from_path(&args.image, args.clusters.get(), args.seed)?;
+
+    if args.derive {
+        let name = args
+            .image
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let selection = selection_from_swatches(palette.into_iter().map(|(c, _)| c).collect())?;
+        return match args.output.derive(selection, &name) {
+            Some(theme) => {
+                args.output.output(&theme, &selection)?;
+                Ok(0)
+            }
+            None => Ok(1),
+        };
+    }
+
+    if args.json {
+        let swatches: Vec<Swatch> = palette
+            .iter()
+            .map(|(c, percentage)| Swatch {
+                color: c.to_string(),
+                percentage: *percentage,
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&swatches)?);
+    } else {
+        for (c, percentage) in palette {
+            println!("{} {:5.1}%", c, percentage * 100.0);
+        }
+    }
+    Ok(0)
+}
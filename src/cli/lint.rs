@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::{
+    export::read_theme,
+    lint::{lint, LintConfig, Rule, Severity},
+};
+use anyhow::Context;
+use clap::Args;
+use std::{fs, path::PathBuf};
+
+#[derive(Args)]
+pub struct LintArgs {
+    /// A `.cosmic-theme` bundle, or a theme in RON
+    theme: PathBuf,
+    /// TOML file with `min-contrast`, `min-accent-distance` and `disabled` rules
+    #[clap(long)]
+    config: Option<PathBuf>,
+    /// WCAG contrast ratio text needs on its surface
+    #[clap(long)]
+    min_contrast: Option<f32>,
+    /// CIEDE2000 difference needed between the accent and destructive colors
+    #[clap(long)]
+    min_accent_distance: Option<f32>,
+    /// Rule to skip: contrast, distinguishable, surface-alpha or missing-optional
+    #[clap(long, multiple_occurrences = true)]
+    disable: Vec<Rule>,
+    /// Fail on warnings as well as errors
+    #[clap(long)]
+    deny_warnings: bool,
+    /// Print the violations as JSON
+    #[clap(long)]
+    json: bool,
+}
+
+pub fn run(args: LintArgs) -> anyhow::Result<i32> {
+    let mut config: LintConfig = match &args.config {
+        Some(path) => toml::from_str(&fs::read_to_string(path)?)
+            .with_context(|| format!("invalid lint config {}", path.display()))?,
+        None => LintConfig::default(),
+    };
+    if let Some(min_contrast) = args.min_contrast {
+        config.min_contrast = min_contrast;
+    }
+    if let Some(min_accent_distance) = args.min_accent_distance {
+        config.min_accent_distance = min_accent_distance;
+    }
+    config.disabled.extend(args.disable);
+
+    // bundles carry the selection as well, which some rules need
+    let (theme, selection) = read_theme(&fs::read(&args.theme)?)
+        .with_context(|| format!("failed to read {}", args.theme.display()))?;
+
+    let violations = lint(&theme, selection.as_ref(), &config);
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&violations)?);
+    } else {
+        for violation in &violations {
+            println!("{}", violation);
+        }
+        if violations.iter().all(|v| v.severity == Severity::Note) {
+            println!("{}: no problems found", args.theme.display());
+        }
+    }
+
+    let threshold = if args.deny_warnings {
+        Severity::Warning
+    } else {
+        Severity::Error
+    };
+    Ok(if violations.iter().any(|v| v.severity >= threshold) {
+        1
+    } else {
+        0
+    })
+}
@@ -0,0 +1,72 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Headless subcommands, run without initializing GTK
+
+use clap::{CommandFactory, Parser, Subcommand};
+use std::env;
+
+mod batch;
+mod derive;
+mod from_image;
+mod lint;
+mod screenshot;
+mod thumbnail;
+
+#[derive(Parser)]
+#[clap(name = "cosmic-theme-editor", version, about)]
+struct Cli {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Derive a theme from selected colors
+    Derive(derive::DeriveArgs),
+    /// Extract a palette from an image
+    FromImage(from_image::FromImageArgs),
+    /// Check a theme for contrast and other problems
+    Lint(lint::LintArgs),
+    /// Derive a theme for every image in a directory
+    Batch(batch::BatchArgs),
+    /// Render the editor's preview of a theme to a PNG
+    Screenshot(screenshot::ScreenshotArgs),
+}
+
+/// Run a subcommand when one is given, returning the exit code.
+/// Without a subcommand the arguments are left for the application.
+pub fn run() -> Option<i32> {
+    let subcommand = env::args_os().nth(1)?;
+    let result = if subcommand == "--thumbnail" {
+        // thumbnailers are called with a fixed set of arguments after the flag
+        let args = env::args_os().take(1).chain(env::args_os().skip(2));
+        thumbnail::run(thumbnail::ThumbnailArgs::parse_from(args))
+    // clap only adds the help subcommand once it builds the command
+    } else if subcommand == "help"
+        || Cli::command()
+            .find_subcommand(subcommand.as_os_str())
+            .is_some()
+    {
+        run_command(Cli::parse_from(env::args_os()).command)
+    } else {
+        return None;
+    };
+
+    Some(match result {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            1
+        }
+    })
+}
+
+fn run_command(command: Command) -> anyhow::Result<i32> {
+    match command {
+        Command::Derive(args) => derive::run(args),
+        Command::FromImage(args) => from_image::run(args),
+        Command::Lint(args) => lint::run(args),
+        Command::Batch(args) => batch::run(args),
+        Command::Screenshot(args) => screenshot::run(args),
+    }
+}
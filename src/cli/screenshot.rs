@@ -0,0 +1,29 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::export::{preview_screenshot_png, read_theme};
+use anyhow::Context;
+use clap::Args;
+use std::{fs, path::PathBuf};
+
+#[derive(Args)]
+pub struct ScreenshotArgs {
+    /// Theme bundle or theme in RON
+    theme: PathBuf,
+    /// PNG to write
+    #[clap(short, long)]
+    output: PathBuf,
+    /// Scale factor of the image
+    #[clap(long, default_value_t = 1.0)]
+    scale: f64,
+}
+
+pub fn run(args: ScreenshotArgs) -> anyhow::Result<i32> {
+    if args.scale.is_nan() || args.scale <= 0.0 {
+        anyhow::bail!("scale must be positive");
+    }
+    let (theme, _) = read_theme(&fs::read(&args.theme)?)
+        .with_context(|| format!("failed to read {}", args.theme.display()))?;
+    fs::write(&args.output, preview_screenshot_png(&theme, args.scale)?)?;
+    println!("wrote {}", args.output.display());
+    Ok(0)
+}
@@ -0,0 +1,28 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::export::{preview_png, read_theme};
+use clap::Parser;
+use gtk4::{gio, prelude::*};
+use std::{ffi::OsString, fs, path::PathBuf};
+
+/// Arguments of the thumbnailer, `--thumbnail <in> <out> <size>`
+#[derive(Parser)]
+pub struct ThumbnailArgs {
+    /// Theme path or URI
+    #[clap(parse(from_os_str))]
+    input: OsString,
+    /// PNG to write
+    #[clap(parse(from_os_str))]
+    output: PathBuf,
+    /// Largest side of the thumbnail, in pixels
+    size: i32,
+}
+
+pub fn run(args: ThumbnailArgs) -> anyhow::Result<i32> {
+    let (data, _) =
+        gio::File::for_commandline_arg(&args.input).load_contents(gio::Cancellable::NONE)?;
+    let (theme, _) = read_theme(&data)?;
+    let size = args.size.max(16);
+    fs::write(&args.output, preview_png(&theme, size, size * 5 / 8)?)?;
+    Ok(0)
+}
@@ -0,0 +1,35 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::metadata::ThemeMetadata;
+use gtk4::{glib, subclass::prelude::*, DropDown, Entry, Label};
+use once_cell::sync::OnceCell;
+use std::{cell::RefCell, rc::Rc};
+
+// Object holding the state
+#[derive(Default)]
+pub struct MetadataEditor {
+    pub author: OnceCell<Entry>,
+    pub description: OnceCell<Entry>,
+    pub license: OnceCell<Entry>,
+    pub tags: OnceCell<Entry>,
+    pub variant: OnceCell<DropDown>,
+    pub created: OnceCell<Label>,
+    pub modified: OnceCell<Label>,
+    pub metadata: Rc<RefCell<ThemeMetadata>>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for MetadataEditor {
+    const NAME: &'static str = "MetadataEditor";
+    type Type = super::MetadataEditor;
+    type ParentType = gtk4::Box;
+}
+
+// Trait shared by all GObjects
+impl ObjectImpl for MetadataEditor {}
+
+// Trait shared by all widgets
+impl WidgetImpl for MetadataEditor {}
+
+// Trait shared by all boxes
+impl BoxImpl for MetadataEditor {}
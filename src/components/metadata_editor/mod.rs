@@ -0,0 +1,199 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+mod imp;
+
+use crate::metadata::{ThemeMetadata, ThemeVariant};
+use cascade::cascade;
+use gettextrs::gettext;
+use gtk4::{
+    glib, prelude::*, subclass::prelude::*, DropDown, Entry, Expander, Grid, Label, Orientation,
+};
+
+glib::wrapper! {
+    pub struct MetadataEditor(ObjectSubclass<imp::MetadataEditor>)
+        @extends gtk4::Box, gtk4::Widget,
+    @implements gtk4::Accessible, gtk4::Buildable, gtk4::ConstraintTarget, gtk4::Orientable;
+}
+
+impl Default for MetadataEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MetadataEditor {
+    pub fn new() -> Self {
+        let self_: Self = glib::Object::new(&[]).expect("Failed to create `MetadataEditor`.");
+        let imp = imp::MetadataEditor::from_instance(&self_);
+
+        cascade! {
+            &self_;
+            ..set_orientation(Orientation::Vertical);
+            ..set_margin_top(4);
+            ..set_margin_bottom(4);
+            ..set_margin_start(8);
+            ..set_margin_end(8);
+        };
+
+        let grid = cascade! {
+            Grid::new();
+            ..set_row_spacing(4);
+            ..set_column_spacing(8);
+            ..set_margin_top(4);
+        };
+        let mut row = 0;
+        let mut attach = |label: String, widget: &gtk4::Widget| {
+            grid.attach(
+                &cascade! {
+                    Label::new(Some(&label));
+                    ..set_xalign(0.0);
+                    ..add_css_class("background-text");
+                },
+                0,
+                row,
+                1,
+                1,
+            );
+            widget.set_hexpand(true);
+            grid.attach(widget, 1, row, 1, 1);
+            row += 1;
+        };
+
+        let entry = |placeholder: &str| {
+            cascade! {
+                Entry::new();
+                ..set_placeholder_text(Some(placeholder));
+                ..add_css_class("background-component");
+                ..add_css_class("border-radius-medium");
+            }
+        };
+        let author = entry(&gettext("Your name"));
+        let description = entry(&gettext("A short description of the theme"));
+        let license = entry("CC-BY-SA-4.0");
+        let tags = entry(&gettext("Comma separated"));
+        let variant_names: Vec<String> = ThemeVariant::ALL.iter().map(|v| v.name()).collect();
+        let variant_names: Vec<&str> = variant_names.iter().map(String::as_str).collect();
+        let variant = DropDown::from_strings(&variant_names);
+        let created = Label::new(None);
+        created.set_xalign(0.0);
+        let modified = Label::new(None);
+        modified.set_xalign(0.0);
+
+        attach(gettext("Author"), author.upcast_ref());
+        attach(gettext("Description"), description.upcast_ref());
+        attach(gettext("License"), license.upcast_ref());
+        attach(gettext("Tags"), tags.upcast_ref());
+        attach(gettext("Variant"), variant.upcast_ref());
+        attach(gettext("Created"), created.upcast_ref());
+        attach(gettext("Modified"), modified.upcast_ref());
+
+        self_.append(&cascade! {
+            Expander::new(Some(&gettext("Details")));
+            ..set_child(Some(&grid));
+        });
+
+        imp.author.set(author).unwrap();
+        imp.description.set(description).unwrap();
+        imp.license.set(license).unwrap();
+        imp.tags.set(tags).unwrap();
+        imp.variant.set(variant).unwrap();
+        imp.created.set(created).unwrap();
+        imp.modified.set(modified).unwrap();
+
+        self_.connect_fields();
+        self_.set_metadata(ThemeMetadata::default());
+
+        self_
+    }
+
+    pub fn metadata(&self) -> ThemeMetadata {
+        imp::MetadataEditor::from_instance(&self)
+            .metadata
+            .borrow()
+            .clone()
+    }
+
+    pub fn set_metadata(&self, metadata: ThemeMetadata) {
+        let imp = imp::MetadataEditor::from_instance(&self);
+        // the entries write back to the metadata as they change, so fill them from a copy
+        imp.metadata.replace(metadata.clone());
+        imp.author.get().unwrap().set_text(&metadata.author);
+        imp.description
+            .get()
+            .unwrap()
+            .set_text(&metadata.description);
+        imp.license.get().unwrap().set_text(&metadata.license);
+        imp.tags.get().unwrap().set_text(&metadata.tags.join(", "));
+        imp.variant.get().unwrap().set_selected(
+            ThemeVariant::ALL
+                .iter()
+                .position(|v| *v == metadata.variant)
+                .unwrap_or_default() as u32,
+        );
+        imp.created
+            .get()
+            .unwrap()
+            .set_text(&Self::format_time(&metadata.created));
+        imp.modified
+            .get()
+            .unwrap()
+            .set_text(&Self::format_time(&metadata.modified));
+    }
+
+    /// Use the variant of a derived theme until the metadata has been saved
+    pub fn suggest_variant(&self, variant: ThemeVariant) {
+        let imp = imp::MetadataEditor::from_instance(&self);
+        if imp.metadata.borrow().created.is_empty() {
+            imp.variant.get().unwrap().set_selected(
+                ThemeVariant::ALL
+                    .iter()
+                    .position(|v| *v == variant)
+                    .unwrap_or_default() as u32,
+            );
+        }
+    }
+
+    fn format_time(time: &str) -> String {
+        glib::DateTime::from_iso8601(time, None)
+            .and_then(|time| time.to_local())
+            .and_then(|time| time.format("%x %X"))
+            .map(|time| time.to_string())
+            .unwrap_or_else(|_| gettext("Not saved yet"))
+    }
+
+    fn connect_fields(&self) {
+        let imp = imp::MetadataEditor::from_instance(&self);
+        let metadata = &imp.metadata;
+
+        imp.author
+            .get()
+            .unwrap()
+            .connect_changed(glib::clone!(@weak metadata => move |author| {
+                metadata.borrow_mut().author = author.text().to_string();
+            }));
+        imp.description.get().unwrap().connect_changed(
+            glib::clone!(@weak metadata => move |description| {
+                metadata.borrow_mut().description = description.text().to_string();
+            }),
+        );
+        imp.license
+            .get()
+            .unwrap()
+            .connect_changed(glib::clone!(@weak metadata => move |license| {
+                metadata.borrow_mut().license = license.text().trim().to_string();
+            }));
+        imp.tags
+            .get()
+            .unwrap()
+            .connect_changed(glib::clone!(@weak metadata => move |tags| {
+                metadata.borrow_mut().set_tags(&tags.text());
+            }));
+        imp.variant.get().unwrap().connect_selected_notify(
+            glib::clone!(@weak metadata => move |variant| {
+                if let Some(v) = ThemeVariant::ALL.get(variant.selected() as usize) {
+                    metadata.borrow_mut().variant = *v;
+                }
+            }),
+        );
+    }
+}
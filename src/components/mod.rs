@@ -1,7 +1,11 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 pub use file_button::*;
+pub use metadata_editor::*;
+pub use syntax_preview::*;
 pub use theme_editor::*;
 
 mod file_button;
+mod metadata_editor;
+mod syntax_preview;
 mod theme_editor;
@@ -0,0 +1,35 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::syntax::SyntaxPalette;
+use gtk4::{glib, subclass::prelude::*, Button, ColorButton, TextBuffer};
+use once_cell::sync::OnceCell;
+use std::{cell::Cell, rc::Rc};
+
+// Object holding the state
+#[derive(Default)]
+pub struct SyntaxPreview {
+    pub color_buttons: OnceCell<Vec<ColorButton>>,
+    pub buffer: OnceCell<TextBuffer>,
+    pub reset: OnceCell<Button>,
+    pub palette: Rc<Cell<Option<SyntaxPalette>>>,
+    /// the palette derived from the last theme, which Reset returns to
+    pub derived: Rc<Cell<Option<SyntaxPalette>>>,
+    /// whether the user changed the palette, which stops it following the theme
+    pub edited: Rc<Cell<bool>>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for SyntaxPreview {
+    const NAME: &'static str = "SyntaxPreview";
+    type Type = super::SyntaxPreview;
+    type ParentType = gtk4::Box;
+}
+
+// Trait shared by all GObjects
+impl ObjectImpl for SyntaxPreview {}
+
+// Trait shared by all widgets
+impl WidgetImpl for SyntaxPreview {}
+
+// Trait shared by all boxes
+impl BoxImpl for SyntaxPreview {}
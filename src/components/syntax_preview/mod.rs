@@ -0,0 +1,246 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+mod imp;
+
+use crate::{
+    syntax::{SyntaxPalette, SyntaxRole},
+    util::SRGBA,
+};
+use cascade::cascade;
+use cosmic_theme::Theme;
+use gettextrs::gettext;
+use gtk4::{
+    glib, prelude::*, subclass::prelude::*, Box, Button, ColorButton, FlowBox, Label, Orientation,
+    SelectionMode, TextBuffer, TextView, WrapMode,
+};
+
+/// Sample code, split into runs highlighted with a syntax role
+const SAMPLE: &[(&str, Option<SyntaxRole>)] = &[
+    (
+        "// Derive a theme from the selected colors\n",
+        Some(SyntaxRole::Comment),
+    ),
+    ("fn", Some(SyntaxRole::Keyword)),
+    (" ", None),
+    ("preview", Some(SyntaxRole::Function)),
+    ("(selection: ", None),
+    ("Selection", Some(SyntaxRole::Type)),
+    (") -> ", None),
+    ("Theme", Some(SyntaxRole::Type)),
+    (" {\n    ", None),
+    ("let", Some(SyntaxRole::Keyword)),
+    (" name = ", None),
+    ("\"Cosmic\"", Some(SyntaxRole::String)),
+    (";\n    ", None),
+    ("let", Some(SyntaxRole::Keyword)),
+    (" radius = ", None),
+    ("8.0", Some(SyntaxRole::Number)),
+    (";\n    ", None),
+    ("derive", Some(SyntaxRole::Function)),
+    ("(name, selection, radius", None),
+    (")?", Some(SyntaxRole::Error)),
+    ("\n}\n", None),
+];
+
+glib::wrapper! {
+    pub struct SyntaxPreview(ObjectSubclass<imp::SyntaxPreview>)
+        @extends gtk4::Box, gtk4::Widget,
+    @implements gtk4::Accessible, gtk4::Buildable, gtk4::ConstraintTarget, gtk4::Orientable;
+}
+
+impl Default for SyntaxPreview {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SyntaxPreview {
+    pub fn new() -> Self {
+        let self_: Self = glib::Object::new(&[]).expect("Failed to create `SyntaxPreview`.");
+        let imp = imp::SyntaxPreview::from_instance(&self_);
+
+        let reset = cascade! {
+            Button::with_label(&gettext("Reset"));
+            ..set_tooltip_text(Some(&gettext("Derive the colors from the theme again")));
+            ..set_sensitive(false);
+            ..add_css_class("background-component");
+            ..add_css_class("border-radius-medium");
+        };
+        cascade! {
+            &self_;
+            ..set_orientation(Orientation::Vertical);
+            ..set_spacing(4);
+            ..set_margin_top(8);
+            ..set_margin_bottom(8);
+            ..set_margin_start(8);
+            ..set_margin_end(8);
+            ..append(&cascade! {
+                Box::new(Orientation::Horizontal, 4);
+                ..append(&cascade! {
+                    Label::new(Some(&gettext("Syntax Highlighting")));
+                    ..set_xalign(0.0);
+                    ..set_hexpand(true);
+                    ..add_css_class("background-text");
+                });
+                ..append(&reset);
+            });
+        };
+
+        let button_box = cascade! {
+            FlowBox::new();
+            ..set_selection_mode(SelectionMode::None);
+            ..set_column_spacing(4);
+            ..set_row_spacing(4);
+        };
+        let color_buttons: Vec<ColorButton> = SyntaxRole::ALL
+            .iter()
+            .map(|role| {
+                let color_button = cascade! {
+                    ColorButton::with_rgba(&SRGBA::default().into());
+                    ..set_title(&role.label());
+                    ..set_use_alpha(true);
+                    ..add_css_class("background-component");
+                };
+                button_box.insert(
+                    &cascade! {
+                        Box::new(Orientation::Horizontal, 4);
+                        ..append(&color_button);
+                        ..append(&cascade! {
+                            Label::new(Some(&role.label()));
+                            ..add_css_class("background-text");
+                        });
+                    },
+                    -1,
+                );
+                color_button
+            })
+            .collect();
+        self_.append(&button_box);
+
+        let buffer = TextBuffer::new(None);
+        for role in SyntaxRole::ALL {
+            buffer.create_tag(Some(role.name()), &[]);
+        }
+        let mut end = buffer.end_iter();
+        for (text, role) in SAMPLE {
+            match role {
+                Some(role) => buffer.insert_with_tags_by_name(&mut end, text, &[role.name()]),
+                None => buffer.insert(&mut end, text),
+            }
+        }
+        self_.append(&cascade! {
+            TextView::with_buffer(&buffer);
+            ..set_editable(false);
+            ..set_monospace(true);
+            ..set_wrap_mode(WrapMode::WordChar);
+            ..set_top_margin(8);
+            ..set_bottom_margin(8);
+            ..set_left_margin(8);
+            ..set_right_margin(8);
+            ..add_css_class("background");
+            ..add_css_class("border-radius-medium");
+        });
+
+        imp.color_buttons.set(color_buttons).unwrap();
+        imp.buffer.set(buffer).unwrap();
+        imp.reset.set(reset).unwrap();
+
+        self_.connect_color_buttons();
+        self_.connect_reset();
+
+        self_
+    }
+
+    /// The palette shown, once one was derived or chosen
+    pub fn palette(&self) -> Option<SyntaxPalette> {
+        imp::SyntaxPreview::from_instance(&self).palette.get()
+    }
+
+    /// The palette, if the user changed it
+    pub fn edited_palette(&self) -> Option<SyntaxPalette> {
+        let imp = imp::SyntaxPreview::from_instance(&self);
+        imp.palette.get().filter(|_| imp.edited.get())
+    }
+
+    /// Show a palette the user chose earlier, such as one stored in a bundle.
+    /// With `None` the palette follows the theme again.
+    pub fn set_palette(&self, palette: Option<SyntaxPalette>) {
+        let imp = imp::SyntaxPreview::from_instance(&self);
+        self.set_edited(palette.is_some());
+        if let Some(palette) = palette.or_else(|| imp.derived.get()) {
+            self.show_palette(palette);
+        }
+    }
+
+    /// Derive the palette from `theme`, and show it unless the user edited the palette
+    pub fn follow_theme(&self, theme: &Theme<SRGBA>) {
+        let imp = imp::SyntaxPreview::from_instance(&self);
+        let derived = SyntaxPalette::derive(theme);
+        imp.derived.set(Some(derived));
+        if !imp.edited.get() {
+            self.show_palette(derived);
+        }
+    }
+
+    fn set_edited(&self, edited: bool) {
+        let imp = imp::SyntaxPreview::from_instance(&self);
+        imp.edited.set(edited);
+        imp.reset.get().unwrap().set_sensitive(edited);
+    }
+
+    fn show_palette(&self, palette: SyntaxPalette) {
+        let imp = imp::SyntaxPreview::from_instance(&self);
+        imp.palette.set(Some(palette));
+        for (role, color_button) in SyntaxRole::ALL.iter().zip(imp.color_buttons.get().unwrap()) {
+            color_button.set_rgba(&palette.get(*role).into());
+        }
+        self.update_tags();
+    }
+
+    fn update_tags(&self) {
+        let imp = imp::SyntaxPreview::from_instance(&self);
+        let palette = match imp.palette.get() {
+            Some(palette) => palette,
+            None => return,
+        };
+        let tags = imp.buffer.get().unwrap().tag_table();
+        for role in SyntaxRole::ALL {
+            if let Some(tag) = tags.lookup(role.name()) {
+                let rgba: gtk4::gdk::RGBA = palette.get(role).into();
+                tag.set_property("foreground-rgba", &rgba);
+            }
+        }
+    }
+
+    fn connect_color_buttons(&self) {
+        let imp = imp::SyntaxPreview::from_instance(&self);
+        let palette = &imp.palette;
+
+        for (role, color_button) in SyntaxRole::ALL.iter().zip(imp.color_buttons.get().unwrap()) {
+            let role = *role;
+            color_button.connect_color_set(
+                glib::clone!(@weak palette, @weak self as self_ => move |color_button| {
+                    // the editor derives a palette from its theme before anything can be edited
+                    let mut p = match palette.get() {
+                        Some(p) => p,
+                        None => return,
+                    };
+                    p.set(role, SRGBA::from(color_button.rgba()));
+                    palette.set(Some(p));
+                    self_.set_edited(true);
+                    self_.update_tags();
+                }),
+            );
+        }
+    }
+
+    fn connect_reset(&self) {
+        let imp = imp::SyntaxPreview::from_instance(&self);
+        imp.reset
+            .get()
+            .unwrap()
+            .connect_clicked(glib::clone!(@weak self as self_ => move |_| {
+                self_.set_palette(None);
+            }));
+    }
+}
@@ -1,11 +1,22 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
-use crate::{components::FileButton, util::SRGBA};
+use crate::{
+    components::{FileButton, MetadataEditor, SyntaxPreview},
+    metadata::ThemeVariant,
+    project::ThemeProject,
+    util::SRGBA,
+};
 use cosmic_theme::{Selection, Theme, ThemeConstraints};
-use gtk4::{glib, subclass::prelude::*, Button, ColorButton, CssProvider, Entry, Switch};
-use once_cell::sync::OnceCell;
+use gtk4::{
+    glib::{self, subclass::Signal},
+    prelude::*,
+    subclass::prelude::*,
+    Button, ColorButton, CssProvider, Entry, Switch, ToggleButton,
+};
+use once_cell::sync::{Lazy, OnceCell};
 use std::{
     cell::{Cell, RefCell},
+    path::PathBuf,
     rc::Rc,
 };
 
@@ -13,10 +24,18 @@ use std::{
 #[derive(Default)]
 pub struct ThemeEditor {
     pub name: OnceCell<Entry>,
+    pub metadata_editor: OnceCell<MetadataEditor>,
     pub save: OnceCell<Button>,
     pub preview: OnceCell<Button>,
+    pub apply_gtk: OnceCell<Button>,
+    pub revert_gtk: OnceCell<Button>,
+    pub paste_palette: OnceCell<Button>,
     pub file_button: OnceCell<FileButton>,
+    pub syntax_preview: OnceCell<SyntaxPreview>,
     pub lighten_elevated_surfaces: OnceCell<Switch>,
+    pub light_toggle: OnceCell<ToggleButton>,
+    pub dark_toggle: OnceCell<ToggleButton>,
+    pub generate_variant: OnceCell<Button>,
     pub background_color_button: OnceCell<ColorButton>,
     pub primary_color_button: OnceCell<ColorButton>,
     pub secondary_color_button: OnceCell<ColorButton>,
@@ -26,7 +45,13 @@ pub struct ThemeEditor {
     pub destructive_color_button: OnceCell<ColorButton>,
     pub constraints: Rc<Cell<ThemeConstraints>>,
     pub selection: Rc<Cell<Selection<SRGBA>>>,
+    /// the variant being edited, its colors are in `selection` and `constraints`
+    pub variant: Rc<Cell<ThemeVariant>>,
+    /// both variants, as they were when last switched between
+    pub project: Rc<Cell<ThemeProject>>,
     pub theme: Rc<RefCell<Theme<SRGBA>>>,
+    /// image the palette was last extracted from, included in theme bundles
+    pub wallpaper: Rc<RefCell<Option<PathBuf>>>,
     pub css_provider: Rc<OnceCell<CssProvider>>,
 }
 
@@ -39,7 +64,20 @@ impl ObjectSubclass for ThemeEditor {
 }
 
 // Trait shared by all GObjects
-impl ObjectImpl for ThemeEditor {}
+impl ObjectImpl for ThemeEditor {
+    fn signals() -> &'static [Signal] {
+        static SIGNALS: Lazy<Vec<Signal>> = Lazy::new(|| {
+            vec![Signal::builder(
+                // Emitted when a new theme is derived or loaded
+                "theme-changed",
+                &[],
+                <()>::static_type().into(),
+            )
+            .build()]
+        });
+        SIGNALS.as_ref()
+    }
+}
 
 // Trait shared by all widgets
 impl WidgetImpl for ThemeEditor {}
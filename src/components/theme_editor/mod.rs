@@ -1,20 +1,28 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
-use crate::{components::FileButton, util::SRGBA};
+use crate::{
+    components::{FileButton, MetadataEditor, SyntaxPreview},
+    export::{self, ThemeBundle, Wallpaper},
+    metadata::{ThemeMetadata, ThemeVariant},
+    project::{split_variant, ThemeProject, VariantSelection},
+    syntax::SyntaxPalette,
+    util::SRGBA,
+};
 
 use cascade::cascade;
-use cosmic_theme::{ColorPicker, Exact, Gtk4Output, Derivation};
+use cosmic_theme::{ColorPicker, Derivation, Exact, Gtk4Output, Selection, Theme};
 use gettextrs::gettext;
 use gtk4::{
-    gio::File,
+    gio::{self, File},
     glib::{self, closure_local},
     prelude::*,
     subclass::prelude::*,
-    Box, Button, ColorButton, CssProvider, Entry, Label, MessageDialog, Orientation,
-    ScrolledWindow, Separator, Switch, Window,
+    Box, Button, ColorButton, CssProvider, DrawingArea, Entry, FileChooserAction,
+    FileChooserNative, Grid, Label, MenuButton, MessageDialog, Orientation, ResponseType,
+    ScrolledWindow, Separator, Switch, ToggleButton, Widget, Window,
 };
 use relm4_macros::view;
-use std::fmt::Display;
+use std::{cell::RefCell, fmt::Display, fs, rc::Rc};
 mod imp;
 
 glib::wrapper! {
@@ -49,6 +57,8 @@ impl ThemeEditor {
         let (destructive_color_box, destructive_color_button) =
             Self::get_color_button(&gettext("Destructive Color"));
 
+        let (export_menu, import_menu) = Self::get_registry_menus();
+
         view! {
             inner = Box {
                 set_orientation: Orientation::Vertical,
@@ -70,6 +80,8 @@ impl ThemeEditor {
                     set_width_request: 160,
                 },
 
+                append: metadata_editor = &MetadataEditor {},
+
                 append = &Box {
                     set_orientation: Orientation::Horizontal,
                     set_spacing: 4,
@@ -93,6 +105,38 @@ impl ThemeEditor {
                     }
                 },
 
+                append = &Box {
+                    set_orientation: Orientation::Horizontal,
+                    set_spacing: 4,
+                    set_margin_top: 4,
+                    set_margin_bottom: 4,
+                    set_margin_start: 4,
+                    set_margin_end: 4,
+
+                    append: light_toggle = &ToggleButton {
+                        set_label: &gettext("Light"),
+                        set_active: imp.variant.get() == ThemeVariant::Light,
+                        add_css_class: "background-component",
+                        add_css_class: "border-radius-medium",
+                    },
+
+                    append: dark_toggle = &ToggleButton {
+                        set_label: &gettext("Dark"),
+                        set_active: imp.variant.get() == ThemeVariant::Dark,
+                        add_css_class: "background-component",
+                        add_css_class: "border-radius-medium",
+                    },
+
+                    append: generate_variant_button = &Button {
+                        add_css_class: "background-component",
+                        add_css_class: "border-radius-medium",
+
+                        set_child = Some(&Label) {
+                            set_text: &gettext("Generate Other Variant"),
+                        }
+                    },
+                },
+
                 append: &background_color_box,
                 append: &primary_color_box,
                 append: &secondary_color_box,
@@ -137,6 +181,70 @@ impl ThemeEditor {
                         }
                     },
 
+                    append: apply_gtk_button = &Button {
+                        set_margin_top: 4,
+                        set_margin_bottom: 4,
+                        set_margin_start: 4,
+                        set_margin_end: 4,
+                        add_css_class: "background-component",
+                        add_css_class: "padding-medium",
+                        add_css_class: "border-radius-medium",
+
+                        set_child = Some(&Label) {
+                            set_text: &gettext("Apply to GTK Apps"),
+                        }
+                    },
+
+                    append: revert_gtk_button = &Button {
+                        set_margin_top: 4,
+                        set_margin_bottom: 4,
+                        set_margin_start: 4,
+                        set_margin_end: 4,
+                        add_css_class: "background-component",
+                        add_css_class: "padding-medium",
+                        add_css_class: "border-radius-medium",
+
+                        set_child = Some(&Label) {
+                            set_text: &gettext("Revert GTK Apps"),
+                        }
+                    },
+
+                    append = &MenuButton {
+                        set_label: &gettext("Export"),
+                        set_menu_model: Some(&export_menu),
+                        set_margin_top: 4,
+                        set_margin_bottom: 4,
+                        set_margin_start: 4,
+                        set_margin_end: 4,
+                        add_css_class: "background-component",
+                        add_css_class: "border-radius-medium",
+                    },
+
+                    append = &MenuButton {
+                        set_label: &gettext("Import"),
+                        set_menu_model: Some(&import_menu),
+                        set_margin_top: 4,
+                        set_margin_bottom: 4,
+                        set_margin_start: 4,
+                        set_margin_end: 4,
+                        add_css_class: "background-component",
+                        add_css_class: "border-radius-medium",
+                    },
+
+                    append: paste_palette_button = &Button {
+                        set_margin_top: 4,
+                        set_margin_bottom: 4,
+                        set_margin_start: 4,
+                        set_margin_end: 4,
+                        add_css_class: "background-component",
+                        add_css_class: "padding-medium",
+                        add_css_class: "border-radius-medium",
+
+                        set_child = Some(&Label) {
+                            set_text: &gettext("Paste Palette"),
+                        }
+                    },
+
                     append: file_button = &FileButton {},
                 },
 
@@ -354,6 +462,8 @@ impl ThemeEditor {
                         },
                     },
                 },
+
+                append: syntax_preview = &SyntaxPreview {},
             }
         };
 
@@ -369,9 +479,20 @@ impl ThemeEditor {
 
         // set widget state
         imp.name.set(name).unwrap();
+        imp.metadata_editor.set(metadata_editor).unwrap();
+        dark_toggle.set_group(Some(&light_toggle));
+        imp.light_toggle.set(light_toggle).unwrap();
+        imp.dark_toggle.set(dark_toggle).unwrap();
+        imp.generate_variant.set(generate_variant_button).unwrap();
         imp.save.set(save_button).unwrap();
         imp.preview.set(preview_button).unwrap();
+        imp.apply_gtk.set(apply_gtk_button).unwrap();
+        imp.revert_gtk.set(revert_gtk_button).unwrap();
+        imp.paste_palette.set(paste_palette_button).unwrap();
         imp.file_button.set(file_button).unwrap();
+        // start from the palette of the initial theme, so edits never begin from a blank one
+        syntax_preview.follow_theme(&imp.theme.borrow());
+        imp.syntax_preview.set(syntax_preview).unwrap();
 
         imp.lighten_elevated_surfaces
             .set(lighten_elevated_surfaces)
@@ -399,17 +520,31 @@ impl ThemeEditor {
         self_.connect_color_buttons();
         self_.connect_control_buttons();
         self_.connect_toggle();
+        self_.connect_variant_toggles();
         self_.connect_file_button();
+        self_.connect_registry_actions();
 
         self_
     }
 
     fn connect_name(&self) {
         let imp = imp::ThemeEditor::from_instance(&self);
+        let metadata_editor = imp.metadata_editor.get().unwrap();
         imp.name.get().unwrap().connect_changed(
-            glib::clone!(@weak imp.theme as theme => move |name| {
+            glib::clone!(@weak imp.theme as theme, @weak metadata_editor => move |name| {
                 let name = name.text();
                 theme.borrow_mut().name = String::from(name.as_str());
+
+                // pick up the details of a theme saved under this name,
+                // otherwise the details describe a new theme
+                match ThemeMetadata::load(name.as_str()) {
+                    Ok(Some(metadata)) => metadata_editor.set_metadata(metadata),
+                    _ => metadata_editor.set_metadata(ThemeMetadata {
+                        created: String::new(),
+                        modified: String::new(),
+                        ..metadata_editor.metadata()
+                    }),
+                }
             }),
         );
     }
@@ -419,16 +554,93 @@ impl ThemeEditor {
         imp.file_button.get().unwrap().connect_closure(
             "image-selected",
             false,
-            closure_local!(@weak-allow-none imp.selection as selection, @weak-allow-none self as self_ => move |_file_button: FileButton, f: File| {
-                dbg!(f.path());
-                if let Some(Ok(s)) = cosmic_theme::palette_from_image(f.path().unwrap()).map(|f| f.try_into()) {
-                    selection.unwrap().set(s);
-                    self_.unwrap().update_color_buttons();
-                }
+            closure_local!(@weak-allow-none self as self_ => move |_file_button: FileButton, f: File| {
+                self_.unwrap().load_image(&f);
             }),
         );
     }
 
+    /// Extract a palette from an image and assign its colors to the selection
+    pub fn load_image(&self, f: &File) {
+        let imp = imp::ThemeEditor::from_instance(&self);
+        let path = match f.path() {
+            Some(path) => path,
+            None => return,
+        };
+        imp.wallpaper.replace(Some(path.clone()));
+        if let Some(Ok(s)) = cosmic_theme::palette_from_image(path).map(|f| f.try_into()) {
+            imp.selection.set(s);
+            self.update_color_buttons();
+        }
+    }
+
+    /// Open a theme bundle or theme for editing, or extract a palette from an image
+    pub fn open_file(&self, f: &File) {
+        let name = f.basename().unwrap_or_default();
+        let (content_type, _) = gio::content_type_guess(Some(&name), &[]);
+        match name.extension().and_then(|extension| extension.to_str()) {
+            Some(export::BUNDLE_EXTENSION) => self.open_bundle(f),
+            Some("ron") => self.open_theme(f),
+            _ if content_type.starts_with("image/") => self.load_image(f),
+            _ => Self::error_dialog(
+                self,
+                format!("{}: {}", gettext("Unsupported file"), name.display()),
+            ),
+        }
+    }
+
+    /// Open a derived theme, recovering the selection from its colors.
+    /// One theme of a light and dark pair opens the project with both variants,
+    /// when the other theme was saved next to it.
+    fn open_theme(&self, f: &File) {
+        let load = |f: &File| -> anyhow::Result<Theme<SRGBA>> {
+            let (data, _) = f.load_contents(gio::Cancellable::NONE)?;
+            Ok(ron::from_str(std::str::from_utf8(&data)?)?)
+        };
+        let loaded = load(f).and_then(|mut theme| {
+            let imp = imp::ThemeEditor::from_instance(&self);
+            let constraints = imp.constraints.get();
+            let variant_selection = |theme: &Theme<SRGBA>| VariantSelection {
+                selection: export::selection_from_theme(theme),
+                constraints,
+            };
+
+            let mut variant = ThemeVariant::of(&theme);
+            let mut project = ThemeProject::default();
+            let partner = split_variant(&theme.name).and_then(|(name, v)| {
+                let partner = f
+                    .parent()?
+                    .child(format!("{}-{}.ron", name, v.other().id()));
+                partner
+                    .query_exists(gio::Cancellable::NONE)
+                    .then(|| (name.to_string(), v, partner))
+            });
+            if let Some((name, v, partner)) = partner {
+                project.set(v.other(), variant_selection(&load(&partner)?));
+                project.set(v, variant_selection(&theme));
+                theme.name = name;
+                variant = v;
+            }
+
+            let metadata = ThemeMetadata::load(&theme.name)?.unwrap_or_default();
+            self.load_bundle(ThemeBundle {
+                variant: Some(variant),
+                ..ThemeBundle::new(
+                    export::selection_from_theme(&theme),
+                    constraints,
+                    theme,
+                    metadata,
+                    project,
+                    None,
+                    None,
+                )
+            })
+        });
+        if let Err(e) = loaded {
+            Self::error_dialog(self, e);
+        }
+    }
+
     fn update_color_buttons(&self) {
         let imp = imp::ThemeEditor::from_instance(&self);
         let selection = imp.selection.get();
@@ -476,6 +688,99 @@ impl ThemeEditor {
             }));
     }
 
+    fn connect_variant_toggles(&self) {
+        let imp = imp::ThemeEditor::from_instance(&self);
+
+        for (variant, toggle) in [
+            (ThemeVariant::Light, imp.light_toggle.get().unwrap()),
+            (ThemeVariant::Dark, imp.dark_toggle.get().unwrap()),
+        ] {
+            toggle.connect_toggled(glib::clone!(@weak self as self_ => move |toggle| {
+                if toggle.is_active() {
+                    self_.switch_variant(variant);
+                }
+            }));
+        }
+
+        imp.generate_variant.get().unwrap().connect_clicked(
+            glib::clone!(@weak self as self_ => move |_| {
+                let imp = imp::ThemeEditor::from_instance(&self_);
+                let variant = imp.variant.get();
+                let mut project = self_.project();
+                project.generate(variant);
+                imp.project.set(project);
+                self_.set_variant_toggle(variant.other());
+            }),
+        );
+    }
+
+    /// The theme project, with the variant being edited up to date
+    fn project(&self) -> ThemeProject {
+        let imp = imp::ThemeEditor::from_instance(&self);
+        let mut project = imp.project.get();
+        project.set(
+            imp.variant.get(),
+            VariantSelection {
+                selection: imp.selection.get(),
+                constraints: imp.constraints.get(),
+            },
+        );
+        project
+    }
+
+    /// Edit the other variant, generating it if it does not exist yet
+    fn switch_variant(&self, variant: ThemeVariant) {
+        let imp = imp::ThemeEditor::from_instance(&self);
+        if imp.variant.get() == variant {
+            return;
+        }
+
+        let mut project = self.project();
+        let v = project.get_or_generate(variant);
+        imp.project.set(project);
+        imp.variant.set(variant);
+        imp.selection.set(v.selection);
+        imp.constraints.set(v.constraints);
+        imp.lighten_elevated_surfaces
+            .get()
+            .unwrap()
+            .set_active(v.constraints.lighten);
+        self.update_color_buttons();
+        imp.preview.get().unwrap().emit_clicked();
+    }
+
+    fn set_variant_toggle(&self, variant: ThemeVariant) {
+        let imp = imp::ThemeEditor::from_instance(&self);
+        match variant {
+            ThemeVariant::Light => imp.light_toggle.get().unwrap().set_active(true),
+            ThemeVariant::Dark => imp.dark_toggle.get().unwrap().set_active(true),
+        }
+    }
+
+    /// Write the derived themes of the variants that were created, named by the project.
+    /// The written themes are returned with the derivation errors, which Preview shows as well.
+    fn write_variants(&self) -> anyhow::Result<(Vec<(ThemeVariant, Theme<SRGBA>)>, Vec<String>)> {
+        let imp = imp::ThemeEditor::from_instance(&self);
+        let name = imp.theme.borrow().name.clone();
+        let mut themes = Vec::new();
+        let mut errors = Vec::new();
+        for (variant, v, theme_name) in self.project().theme_names(&name) {
+            let Derivation {
+                derived: mut theme,
+                errors: variant_errors,
+            } = Exact::new(v.selection, v.constraints).theme_derivation();
+            errors.extend(
+                variant_errors
+                    .iter()
+                    .map(|e| format!("{} ({}): {}", theme_name, variant.id(), e)),
+            );
+            theme.name = theme_name;
+            theme.write().map_err(|e| anyhow::anyhow!("{}", e))?;
+            themes.push((variant, theme));
+        }
+        Ok((themes, errors))
+    }
+
     fn connect_color_buttons(&self) {
         let imp = imp::ThemeEditor::from_instance(&self);
         let selection = &imp.selection;
@@ -577,35 +882,58 @@ impl ThemeEditor {
         let theme = &imp.theme;
         let constraints = &imp.constraints;
         let css_provider = &imp.css_provider;
+        let syntax_preview = imp.syntax_preview.get().unwrap();
+        let metadata_editor = imp.metadata_editor.get().unwrap();
 
         imp.save.get().unwrap().connect_clicked(
-            glib::clone!(@weak selection, @weak theme, @weak constraints => move |save| {
+            glib::clone!(@weak theme, @weak self as self_ => move |save| {
                 println!("saving the theme...");
                 if theme.borrow().name != "" {
-                    if let Err(e) = theme.borrow().write() {
-                        let window = save.root().map(|root| {
-                            if let Ok(w) = root.downcast::<Window>() {
-                                Some(w)
-                            } else {
-                                None
-                            }
-                        }).unwrap_or_default();
-                        if let Some(window) = window {
-                            glib::MainContext::default().spawn_local(Self::dialog(window, format!("{}", e)));
+                    match self_.save() {
+                        Err(e) => Self::error_dialog(save, e),
+                        Ok(errors) if !errors.is_empty() => {
+                            Self::error_dialog(save, errors.join("\n\n"))
                         }
-
+                        Ok(_) => {}
                     }
                 }
             }),
         );
 
+        imp.apply_gtk
+            .get()
+            .unwrap()
+            .connect_clicked(glib::clone!(@weak theme => move |apply| {
+                if let Err(e) = export::install_gtk_theme(&theme.borrow()) {
+                    Self::error_dialog(apply, e);
+                }
+            }));
+
+        imp.revert_gtk
+            .get()
+            .unwrap()
+            .connect_clicked(move |revert| {
+                if let Err(e) = export::revert_gtk_theme() {
+                    Self::error_dialog(revert, e);
+                }
+            });
+
+        imp.paste_palette.get().unwrap().connect_clicked(
+            glib::clone!(@weak self as self_ => move |_| {
+                self_.paste_palette();
+            }),
+        );
+
         imp.preview.get().unwrap().connect_clicked(
-            glib::clone!(@weak selection, @weak theme, @weak constraints, @weak css_provider, @weak self as parent => move |self_| {
+            glib::clone!(@weak selection, @weak theme, @weak constraints, @weak css_provider, @weak syntax_preview, @weak metadata_editor, @weak self as parent => move |self_| {
                 println!("generating new theme");
                 let picker = Exact::new(selection.get(), constraints.get());
                 let Derivation {derived: new_theme, errors} = picker.theme_derivation();
                     dbg!(&new_theme);
                     theme.replace(new_theme);
+                    parent.emit_by_name::<()>("theme-changed", &[]);
+                    syntax_preview.follow_theme(&theme.borrow());
+                    metadata_editor.suggest_variant(ThemeVariant::of(&theme.borrow()));
                     let preview_css = theme.borrow().as_css();
                     println!("{}", &preview_css);
 
@@ -628,6 +956,530 @@ impl ThemeEditor {
         );
     }
 
+    fn get_registry_menus() -> (gio::Menu, gio::Menu) {
+        let export_formats = gio::Menu::new();
+        for exporter in export::exporters(None) {
+            let item = gio::MenuItem::new(Some(exporter.name()), None);
+            item.set_action_and_target_value(
+                Some("editor.export"),
+                Some(&exporter.id().to_variant()),
+            );
+            export_formats.append_item(&item);
+        }
+        let export_menu = gio::Menu::new();
+        export_menu.append(
+            Some(&gettext("COSMIC Theme Bundle")),
+            Some("editor.export-bundle"),
+        );
+        let preview_scales = gio::Menu::new();
+        for scale in [1.0, 2.0, 3.0] {
+            let item = gio::MenuItem::new(Some(&format!("{}×", scale)), None);
+            item.set_action_and_target_value(
+                Some("editor.export-preview-image"),
+                Some(&scale.to_variant()),
+            );
+            preview_scales.append_item(&item);
+        }
+        export_menu.append_submenu(Some(&gettext("Export Preview Image")), &preview_scales);
+        export_menu.append_section(None, &export_formats);
+
+        let import_formats = gio::Menu::new();
+        for importer in export::importers() {
+            let item = gio::MenuItem::new(Some(importer.name()), None);
+            item.set_action_and_target_value(
+                Some("editor.import"),
+                Some(&importer.name().to_variant()),
+            );
+            import_formats.append_item(&item);
+        }
+        let import_menu = gio::Menu::new();
+        import_menu.append(
+            Some(&gettext("COSMIC Theme Bundle")),
+            Some("editor.import-bundle"),
+        );
+        import_menu.append_section(None, &import_formats);
+        (export_menu, import_menu)
+    }
+
+    fn connect_registry_actions(&self) {
+        let imp = imp::ThemeEditor::from_instance(&self);
+        let selection = &imp.selection;
+        let constraints = &imp.constraints;
+        let theme = &imp.theme;
+        let syntax_preview = imp.syntax_preview.get().unwrap();
+
+        let export_action = gio::SimpleAction::new("export", Some(glib::VariantTy::STRING));
+        export_action.connect_activate(
+            glib::clone!(@weak selection, @weak constraints, @weak theme, @weak syntax_preview, @weak self as self_ => move |_, id| {
+                let id = id.and_then(|id| id.str()).unwrap_or_default().to_string();
+                // export the current selection even if it was not previewed yet
+                let mut derived = Exact::new(selection.get(), constraints.get()).theme_derivation().derived;
+                derived.name = theme.borrow().name.clone();
+                let palette = syntax_preview
+                    .edited_palette()
+                    .unwrap_or_else(|| SyntaxPalette::derive(&derived));
+                let exporter = match export::exporters(Some(palette))
+                    .into_iter()
+                    .find(|exporter| exporter.id() == id)
+                {
+                    Some(exporter) => exporter,
+                    None => return,
+                };
+                let file_name = exporter.file_name(&derived.name);
+                Self::choose_file(
+                    &self_,
+                    &format!("{} {}", gettext("Export"), exporter.name()),
+                    FileChooserAction::Save,
+                    Some(&file_name),
+                    glib::clone!(@weak selection, @weak self_ => move |f| {
+                        let written = exporter.export(&derived, &selection.get()).and_then(|output| {
+                            f.path().map(|p| fs::write(p, output)).transpose()?;
+                            Ok(())
+                        });
+                        if let Err(e) = written {
+                            Self::error_dialog(&self_, e);
+                        }
+                    }),
+                );
+            }),
+        );
+
+        let import_action = gio::SimpleAction::new("import", Some(glib::VariantTy::STRING));
+        import_action.connect_activate(glib::clone!(@weak self as self_ => move |_, name| {
+            let name = name.and_then(|name| name.str()).unwrap_or_default().to_string();
+            let importer = match export::importers()
+                .into_iter()
+                .find(|importer| importer.name() == name)
+            {
+                Some(importer) => importer,
+                None => return,
+            };
+            Self::choose_file(
+                &self_,
+                &format!("{} {}", gettext("Import"), importer.name()),
+                FileChooserAction::Open,
+                None,
+                glib::clone!(@weak self_ => move |f| {
+                    let imported = f
+                        .load_contents(gio::Cancellable::NONE)
+                        .map_err(anyhow::Error::from)
+                        .and_then(|(data, _)| importer.import(&data));
+                    match imported {
+                        Ok(s) => self_.confirm_selection(s),
+                        Err(e) => Self::error_dialog(&self_, e),
+                    }
+                }),
+            );
+        }));
+
+        let export_bundle_action = gio::SimpleAction::new("export-bundle", None);
+        export_bundle_action.connect_activate(glib::clone!(@weak self as self_ => move |_, _| {
+            let bundle = match self_.bundle() {
+                Ok(bundle) => bundle,
+                Err(e) => return Self::error_dialog(&self_, e),
+            };
+            Self::choose_file(
+                &self_,
+                &gettext("Export Theme Bundle"),
+                FileChooserAction::Save,
+                Some(&bundle.file_name()),
+                glib::clone!(@weak self_ => move |f| {
+                    let written = bundle.to_bytes().and_then(|output| {
+                        f.path().map(|p| fs::write(p, output)).transpose()?;
+                        Ok(())
+                    });
+                    if let Err(e) = written {
+                        Self::error_dialog(&self_, e);
+                    }
+                }),
+            );
+        }));
+
+        let export_preview_action =
+            gio::SimpleAction::new("export-preview-image", Some(glib::VariantTy::DOUBLE));
+        export_preview_action.connect_activate(
+            glib::clone!(@weak theme, @weak self as self_ => move |_, scale| {
+                let scale = scale.and_then(|scale| scale.get::<f64>()).unwrap_or(1.0);
+                Self::choose_file(
+                    &self_,
+                    &gettext("Export Preview Image"),
+                    FileChooserAction::Save,
+                    Some(&format!("{}.png", theme.borrow().name)),
+                    glib::clone!(@weak theme, @weak self_ => move |f| {
+                        let written = export::preview_screenshot_png(&theme.borrow(), scale).and_then(|output| {
+                            f.path().map(|p| fs::write(p, output)).transpose()?;
+                            Ok(())
+                        });
+                        if let Err(e) = written {
+                            Self::error_dialog(&self_, e);
+                        }
+                    }),
+                );
+            }),
+        );
+
+        let import_bundle_action = gio::SimpleAction::new("import-bundle", None);
+        import_bundle_action.connect_activate(glib::clone!(@weak self as self_ => move |_, _| {
+            Self::choose_file(
+                &self_,
+                &gettext("Import Theme Bundle"),
+                FileChooserAction::Open,
+                None,
+                glib::clone!(@weak self_ => move |f| self_.open_bundle(&f)),
+            );
+        }));
+
+        let actions = gio::SimpleActionGroup::new();
+        actions.add_action(&export_action);
+        actions.add_action(&import_action);
+        actions.add_action(&export_bundle_action);
+        actions.add_action(&export_preview_action);
+        actions.add_action(&import_bundle_action);
+        self.insert_action_group("editor", Some(&actions));
+    }
+
+    /// Bundle the current theme with the image its palette came from
+    fn bundle(&self) -> anyhow::Result<ThemeBundle> {
+        let imp = imp::ThemeEditor::from_instance(&self);
+        let wallpaper = imp
+            .wallpaper
+            .borrow()
+            .as_ref()
+            .map(|path| -> anyhow::Result<Wallpaper> {
+                Ok(Wallpaper {
+                    extension: path
+                        .extension()
+                        .and_then(|extension| extension.to_str())
+                        .unwrap_or("png")
+                        .to_string(),
+                    data: fs::read(path)?,
+                })
+            })
+            .transpose()?;
+        Ok(ThemeBundle {
+            variant: Some(imp.variant.get()),
+            ..ThemeBundle::new(
+                imp.selection.get(),
+                imp.constraints.get(),
+                imp.theme.borrow().clone(),
+                imp.metadata_editor.get().unwrap().metadata(),
+                self.project(),
+                imp.syntax_preview.get().unwrap().edited_palette(),
+                wallpaper,
+            )
+        })
+    }
+
+    /// Open a `.cosmic-theme` bundle for editing
+    fn open_bundle(&self, f: &File) {
+        let loaded = f
+            .load_contents(gio::Cancellable::NONE)
+            .map_err(anyhow::Error::from)
+            .and_then(|(data, _)| ThemeBundle::from_bytes(&data))
+            .and_then(|bundle| self.load_bundle(bundle));
+        if let Err(e) = loaded {
+            Self::error_dialog(self, e);
+        }
+    }
+
+    fn load_bundle(&self, bundle: ThemeBundle) -> anyhow::Result<()> {
+        let imp = imp::ThemeEditor::from_instance(&self);
+        if let Some(wallpaper) = bundle.wallpaper {
+            let dir = glib::user_data_dir()
+                .join("cosmic-theme-editor")
+                .join("wallpapers");
+            fs::create_dir_all(&dir)?;
+            let path = dir.join(wallpaper.file_name(&bundle.theme.name));
+            fs::write(&path, wallpaper.data)?;
+            imp.wallpaper.replace(Some(path));
+        }
+
+        // switch variants without storing the colors being replaced
+        let variant = bundle
+            .variant
+            .unwrap_or_else(|| ThemeVariant::of(&bundle.theme));
+        imp.project.set(bundle.project);
+        imp.variant.set(variant);
+        self.set_variant_toggle(variant);
+
+        imp.selection.set(bundle.selection);
+        imp.constraints.set(bundle.constraints);
+        imp.lighten_elevated_surfaces
+            .get()
+            .unwrap()
+            .set_active(bundle.constraints.lighten);
+        imp.name.get().unwrap().set_text(&bundle.theme.name);
+        imp.metadata_editor
+            .get()
+            .unwrap()
+            .set_metadata(bundle.metadata);
+        let syntax_preview = imp.syntax_preview.get().unwrap();
+        syntax_preview.set_palette(bundle.syntax);
+        syntax_preview.follow_theme(&bundle.theme);
+        imp.css_provider
+            .get()
+            .unwrap()
+            .load_from_data(bundle.theme.as_css().as_bytes());
+        imp.theme.replace(bundle.theme);
+        self.update_color_buttons();
+        self.emit_by_name::<()>("theme-changed", &[]);
+        Ok(())
+    }
+
+    /// Set one color of the selection, named like the `Selection` field
+    pub fn set_selection_color(&self, role: &str, color: SRGBA) -> anyhow::Result<()> {
+        let imp = imp::ThemeEditor::from_instance(&self);
+        let mut selection = imp.selection.get();
+        export::set_selection_color(&mut selection, role, color)?;
+        imp.selection.set(selection);
+        self.update_color_buttons();
+        Ok(())
+    }
+
+    /// The theme as last derived or loaded
+    pub fn theme(&self) -> Theme<SRGBA> {
+        imp::ThemeEditor::from_instance(&self)
+            .theme
+            .borrow()
+            .clone()
+    }
+
+    /// Derive the theme from the selection, as the Preview button does
+    pub fn preview(&self) {
+        let imp = imp::ThemeEditor::from_instance(&self);
+        imp.preview.get().unwrap().emit_clicked();
+    }
+
+    /// Write the theme, its details and the user's templates, as the Save button does.
+    /// Derivation errors are returned once everything is written.
+    pub fn save(&self) -> anyhow::Result<Vec<String>> {
+        let imp = imp::ThemeEditor::from_instance(&self);
+        let name = imp.theme.borrow().name.clone();
+        if name.is_empty() {
+            anyhow::bail!("the theme has no name");
+        }
+        let (themes, errors) = self.write_variants()?;
+
+        let metadata_editor = imp.metadata_editor.get().unwrap();
+        let mut metadata = metadata_editor.metadata();
+        let saved = metadata.save(&name);
+        metadata_editor.set_metadata(metadata);
+        saved?;
+        export::render_user_templates(&themes)?;
+        Ok(errors)
+    }
+
+    /// Show the colors of an imported selection, and apply them if the user accepts
+    fn confirm_selection(&self, s: Selection<SRGBA>) {
+        let window = match Self::window(self) {
+            Some(window) => window,
+            None => return,
+        };
+
+        let roles = [
+            (gettext("Background Color"), Some(s.background)),
+            (
+                gettext("Primary Container Color"),
+                Some(s.primary_container),
+            ),
+            (
+                gettext("Secondary Container Color"),
+                Some(s.secondary_container),
+            ),
+            (gettext("Accent Color"), Some(s.accent)),
+            (gettext("Accent Text Color"), s.accent_text),
+            (gettext("Accent Nav Text Color"), s.accent_nav_handle_text),
+            (gettext("Destructive Color"), Some(s.destructive)),
+        ];
+        let swatches = cascade! {
+            Grid::new();
+            ..set_row_spacing(4);
+            ..set_column_spacing(8);
+        };
+        for (row, (label, color)) in roles.into_iter().enumerate() {
+            let row = row as i32;
+            swatches.attach(&Self::get_swatch(color), 0, row, 1, 1);
+            swatches.attach(
+                &cascade! {
+                    Label::new(Some(&label));
+                    ..set_xalign(0.0);
+                },
+                1,
+                row,
+                1,
+                1,
+            );
+            let hex = color
+                .map(|c| c.to_string())
+                .unwrap_or_else(|| gettext("Derived"));
+            swatches.attach(&Label::new(Some(&hex)), 2, row, 1, 1);
+        }
+
+        let msg_dialog = MessageDialog::builder()
+            .transient_for(&window)
+            .modal(true)
+            .buttons(gtk4::ButtonsType::OkCancel)
+            .text(&gettext("Apply the imported colors?"))
+            .build();
+        cascade! {
+            msg_dialog.message_area().downcast::<Box>().unwrap();
+            ..set_margin_top(8);
+            ..set_margin_bottom(8);
+            ..set_margin_start(8);
+            ..set_margin_end(8);
+            ..append(&swatches);
+        };
+
+        glib::MainContext::default().spawn_local(glib::clone!(@weak self as self_ => async move {
+            let response = msg_dialog.run_future().await;
+            msg_dialog.close();
+            if response == ResponseType::Ok {
+                let imp = imp::ThemeEditor::from_instance(&self_);
+                imp.selection.set(s);
+                self_.update_color_buttons();
+            }
+        }));
+    }
+
+    /// Ask for a pasted palette, and assign its colors to roles like those of an image
+    fn paste_palette(&self) {
+        let window = match Self::window(self) {
+            Some(window) => window,
+            None => return,
+        };
+
+        view! {
+            paste_box = Box {
+                set_orientation: Orientation::Vertical,
+                set_spacing: 8,
+
+                append: entry = &Entry {
+                    set_placeholder_text: Some("#1b1b1b, #2e3440, … or 264653-2a9d8f-e9c46a"),
+                    set_activates_default: true,
+                },
+
+                append: swatches = &Box {
+                    set_orientation: Orientation::Horizontal,
+                    set_spacing: 4,
+                    set_height_request: 24,
+                },
+            }
+        };
+
+        entry.connect_changed(glib::clone!(@weak swatches => move |entry| {
+            while let Some(child) = swatches.first_child() {
+                swatches.remove(&child);
+            }
+            for c in export::swatches_from_text(&entry.text()) {
+                swatches.append(&Self::get_swatch(Some(c)));
+            }
+        }));
+
+        // start from the clipboard when it holds a palette
+        let clipboard = self.clipboard();
+        glib::MainContext::default().spawn_local(glib::clone!(@weak entry => async move {
+            if let Ok(Some(text)) = clipboard.read_text_future().await {
+                if entry.text().is_empty() && !export::swatches_from_text(&text).is_empty() {
+                    entry.set_text(&text);
+                }
+            }
+        }));
+
+        let msg_dialog = MessageDialog::builder()
+            .transient_for(&window)
+            .modal(true)
+            .buttons(gtk4::ButtonsType::OkCancel)
+            .text(&gettext("Paste Palette"))
+            .secondary_text(&gettext(
+                "Paste hex colors separated by commas or spaces, or a coolors.co palette.",
+            ))
+            .build();
+        msg_dialog.set_default_response(ResponseType::Ok);
+        cascade! {
+            msg_dialog.message_area().downcast::<Box>().unwrap();
+            ..set_margin_top(8);
+            ..set_margin_bottom(8);
+            ..set_margin_start(8);
+            ..set_margin_end(8);
+            ..append(&paste_box);
+        };
+
+        glib::MainContext::default().spawn_local(glib::clone!(@weak self as self_ => async move {
+            let response = msg_dialog.run_future().await;
+            msg_dialog.close();
+            if response != ResponseType::Ok {
+                return;
+            }
+            match export::selection_from_swatches(export::swatches_from_text(&entry.text())) {
+                Ok(s) => {
+                    let imp = imp::ThemeEditor::from_instance(&self_);
+                    imp.selection.set(s);
+                    self_.update_color_buttons();
+                }
+                Err(e) => Self::error_dialog(&self_, e),
+            }
+        }));
+    }
+
+    fn get_swatch(color: Option<SRGBA>) -> DrawingArea {
+        cascade! {
+            DrawingArea::new();
+            ..set_content_width(24);
+            ..set_content_height(24);
+            ..set_draw_func(move |_, cr, width, height| {
+                if let Some(c) = color {
+                    cr.set_source_rgba(c.red.into(), c.green.into(), c.blue.into(), c.alpha.into());
+                    cr.rectangle(0.0, 0.0, width.into(), height.into());
+                    let _ = cr.fill();
+                }
+            });
+        }
+    }
+
+    fn choose_file<F: Fn(File) + 'static>(
+        widget: &impl IsA<Widget>,
+        title: &str,
+        action: FileChooserAction,
+        current_name: Option<&str>,
+        on_file: F,
+    ) {
+        let file_chooser = FileChooserNative::new(
+            Some(title),
+            Self::window(widget).as_ref(),
+            action,
+            None,
+            None,
+        );
+        if let Some(name) = current_name {
+            file_chooser.set_current_name(name);
+        }
+
+        // native dialogs are not kept alive by GTK, so hold a reference until they respond
+        let keep_alive = Rc::new(RefCell::new(Some(file_chooser.clone())));
+        file_chooser.connect_response(move |file_chooser, response| {
+            keep_alive.borrow_mut().take();
+            if response != ResponseType::Accept {
+                return;
+            }
+            if let Some(f) = file_chooser.file() {
+                on_file(f);
+            }
+        });
+        file_chooser.show();
+    }
+
+    fn window(widget: &impl IsA<Widget>) -> Option<Window> {
+        widget
+            .root()
+            .and_then(|root| root.downcast::<Window>().ok())
+    }
+
+    fn error_dialog<T: Display + 'static>(widget: &impl IsA<Widget>, msg: T) {
+        if let Some(window) = Self::window(widget) {
+            glib::MainContext::default().spawn_local(Self::dialog(window, msg));
+        }
+    }
+
     async fn dialog<T: Display>(window: Window, msg: T) {
         let msg_dialog = MessageDialog::builder()
             .transient_for(&window)
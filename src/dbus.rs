@@ -0,0 +1,203 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! D-Bus interface for driving the editor from other applications
+
+use crate::{application::ThemeEditorApplication, components::ThemeEditor, util::rgba_from_hex};
+use anyhow::{anyhow, bail};
+use gtk4::{gio, glib, prelude::*};
+use ron::ser::PrettyConfig;
+
+pub const INTERFACE: &str = "com.System76.CosmicThemeEditor";
+const ERROR: &str = "com.System76.CosmicThemeEditor.Error";
+
+const INTROSPECTION: &str = r#"
+<node>
+  <interface name="com.System76.CosmicThemeEditor">
+    <method name="SetSelectionColor">
+      <arg type="s" name="role" direction="in"/>
+      <arg type="s" name="color" direction="in"/>
+    </method>
+    <method name="LoadImage">
+      <arg type="s" name="path" direction="in"/>
+    </method>
+    <method name="Preview"/>
+    <method name="Save">
+      <arg type="as" name="errors" direction="out"/>
+    </method>
+    <method name="GetDerivedTheme">
+      <arg type="s" name="theme" direction="out"/>
+    </method>
+    <signal name="ThemeChanged">
+      <arg type="s" name="name"/>
+    </signal>
+  </interface>
+</node>
+"#;
+
+/// Export the interface on the application's object path
+pub fn register(app: &ThemeEditorApplication) -> anyhow::Result<()> {
+    let (connection, object_path) = match (app.dbus_connection(), app.dbus_object_path()) {
+        (Some(connection), Some(object_path)) => (connection, object_path),
+        _ => return Ok(()),
+    };
+    // method calls are dispatched on the main context the object is registered from,
+    // the only thread the application may be upgraded on
+    let app = glib::SendWeakRef::from(app.downgrade());
+    export(&connection, &object_path, move |method, parameters| {
+        let app = app
+            .upgrade()
+            .ok_or_else(|| anyhow!("the editor is not running"))?;
+        handle_method(&app, method, parameters)
+    })?;
+    Ok(())
+}
+
+/// Register the interface, answering method calls with `handler` and its errors with a D-Bus error
+fn export<F>(
+    connection: &gio::DBusConnection,
+    object_path: &str,
+    handler: F,
+) -> anyhow::Result<gio::RegistrationId>
+where
+    F: Fn(&str, &glib::Variant) -> anyhow::Result<Option<glib::Variant>> + Send + Sync + 'static,
+{
+    let node = gio::DBusNodeInfo::for_xml(INTROSPECTION)?;
+    let interface = node
+        .lookup_interface(INTERFACE)
+        .ok_or_else(|| anyhow!("missing {} in the introspection data", INTERFACE))?;
+
+    let id = connection.register_object(
+        object_path,
+        &interface,
+        move |_, _, _, _, method, parameters, invocation| match handler(method, &parameters) {
+            Ok(value) => invocation.return_value(value.as_ref()),
+            Err(e) => invocation.return_dbus_error(ERROR, &e.to_string()),
+        },
+        // the interface has no properties
+        |_, _, _, _, _| ().to_variant(),
+        |_, _, _, _, _, _| false,
+    )?;
+    Ok(id)
+}
+
+/// Emit `ThemeChanged` whenever the editor derives or loads a theme
+pub fn connect_signals(app: &ThemeEditorApplication, theme_editor: &ThemeEditor) {
+    let (connection, object_path) = match (app.dbus_connection(), app.dbus_object_path()) {
+        (Some(connection), Some(object_path)) => (connection, object_path),
+        _ => return,
+    };
+    theme_editor.connect_closure(
+        "theme-changed",
+        false,
+        glib::closure_local!(move |theme_editor: ThemeEditor| {
+            let name = theme_editor.theme().name;
+            if let Err(e) = connection.emit_signal(
+                None,
+                &object_path,
+                INTERFACE,
+                "ThemeChanged",
+                Some(&(name,).to_variant()),
+            ) {
+                log::warn!("failed to emit ThemeChanged: {}", e);
+            }
+        }),
+    );
+}
+
+fn handle_method(
+    app: &ThemeEditorApplication,
+    method: &str,
+    parameters: &glib::Variant,
+) -> anyhow::Result<Option<glib::Variant>> {
+    // the editor is created on activation, which a D-Bus call may precede
+    let theme_editor = match app.theme_editor() {
+        Some(theme_editor) => theme_editor,
+        None => {
+            app.activate();
+            app.theme_editor()
+                .ok_or_else(|| anyhow!("the editor is not running"))?
+        }
+    };
+    let invalid = || anyhow!("invalid arguments for {}: {}", method, parameters);
+
+    match method {
+        "SetSelectionColor" => {
+            let (role, color) = parameters.get::<(String, String)>().ok_or_else(invalid)?;
+            theme_editor.set_selection_color(&role, rgba_from_hex(&color)?)?;
+        }
+        "LoadImage" => {
+            let (path,) = parameters.get::<(String,)>().ok_or_else(invalid)?;
+            theme_editor.load_image(&gio::File::for_path(path));
+        }
+        "Preview" => theme_editor.preview(),
+        "Save" => {
+            // the themes are written even if deriving them gave errors
+            let errors = theme_editor.save()?;
+            return Ok(Some((errors,).to_variant()));
+        }
+        "GetDerivedTheme" => {
+            let theme = ron::ser::to_string_pretty(&theme_editor.theme(), PrettyConfig::new())?;
+            return Ok(Some((theme,).to_variant()));
+        }
+        _ => bail!("unknown method {}", method),
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OBJECT_PATH: &str = "/com/System76/CosmicThemeEditor";
+
+    fn call(connection: &gio::DBusConnection, method: &str) -> Result<glib::Variant, glib::Error> {
+        glib::MainContext::ref_thread_default().block_on(connection.call_future(
+            connection.unique_name().as_deref(),
+            OBJECT_PATH,
+            INTERFACE,
+            method,
+            None,
+            None,
+            gio::DBusCallFlags::NONE,
+            -1,
+        ))
+    }
+
+    #[test]
+    fn session_bus() {
+        let bus = gio::TestDBus::new(gio::TestDBusFlags::NONE);
+        bus.up();
+        let context = glib::MainContext::new();
+        context
+            .with_thread_default(|| {
+                let connection = gio::DBusConnection::for_address_sync(
+                    &bus.bus_address().unwrap(),
+                    gio::DBusConnectionFlags::AUTHENTICATION_CLIENT
+                        | gio::DBusConnectionFlags::MESSAGE_BUS_CONNECTION,
+                    None,
+                    gio::Cancellable::NONE,
+                )
+                .unwrap();
+                export(&connection, OBJECT_PATH, |method, _| match method {
+                    "GetDerivedTheme" => Ok(Some(("(\n)".to_string(),).to_variant())),
+                    "Save" => bail!("the theme has no name"),
+                    _ => Ok(None),
+                })
+                .unwrap();
+
+                let reply = call(&connection, "GetDerivedTheme").unwrap();
+                assert_eq!(reply.get::<(String,)>(), Some(("(\n)".to_string(),)));
+                assert_eq!(call(&connection, "Preview").unwrap(), ().to_variant());
+
+                let e = call(&connection, "Save").unwrap_err();
+                assert!(e.message().contains(ERROR));
+                assert!(e.message().contains("the theme has no name"));
+
+                // methods outside the introspection data never reach the handler
+                assert!(call(&connection, "Quit").is_err());
+                connection.close_sync(gio::Cancellable::NONE).unwrap();
+            })
+            .unwrap();
+        bus.down();
+    }
+}
@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! [Base16](https://github.com/chriskempson/base16) and
+//! [Base24](https://github.com/tinted-theming/base24) color schemes
+
+use crate::{
+    syntax::SyntaxPalette,
+    util::{hex_from_rgba, over, rgba_from_hex, SRGBA},
+};
+use anyhow::anyhow;
+use cosmic_theme::{Selection, Theme};
+use serde_yaml::Value;
+use std::fmt::Write;
+
+/// Map a Base16 or Base24 scheme onto a selection.
+/// Both the original flat format and the newer `palette:` format are supported.
+pub fn selection_from_base16(yaml: &str) -> anyhow::Result<Selection<SRGBA>> {
+    let scheme: Value = serde_yaml::from_str(yaml)?;
+    let palette = scheme.get("palette").unwrap_or(&scheme);
+    let base = |name: &str| {
+        palette
+            .get(name)
+            .or_else(|| palette.get(name.to_lowercase()))
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("missing {} in scheme", name))
+            .and_then(rgba_from_hex)
+    };
+
+    Ok(Selection {
+        background: base("base00")?,
+        primary_container: base("base01")?,
+        secondary_container: base("base02")?,
+        accent: base("base0D")?,
+        destructive: base("base08")?,
+        // Base24 has a bright blue which reads better as text
+        accent_text: base("base16").or_else(|_| base("base0D")).ok(),
+        ..Default::default()
+    })
+}
+
+/// Write the derived theme as a Base16 scheme, using the syntax palette for the accent colors
+pub fn base16_from_theme(theme: &Theme<SRGBA>, syntax: &SyntaxPalette) -> String {
+    let bg = &theme.background;
+    let primary = &theme.primary;
+    let secondary = &theme.secondary;
+    let surface = &bg.container;
+    let c = |color: &SRGBA| hex_from_rgba(&over(color, surface))[1..7].to_string();
+
+    let bases = [
+        c(&bg.container),
+        c(&primary.container),
+        c(&secondary.container),
+        c(&syntax.comment),
+        c(&primary.container_text_opacity_80),
+        c(&bg.container_text),
+        c(&primary.container_text),
+        c(&secondary.container_text),
+        c(&theme.destructive.destructive.default),
+        c(&syntax.number),
+        c(&syntax.type_),
+        c(&syntax.string),
+        c(&theme.accent.accent),
+        c(&syntax.function),
+        c(&syntax.keyword),
+        c(&syntax.error),
+    ];
+
+    let mut yaml = String::new();
+    let _ = writeln!(yaml, "scheme: {:?}", theme.name);
+    let _ = writeln!(yaml, "author: {:?}", crate::APP_TITLE);
+    for (i, base) in bases.iter().enumerate() {
+        let _ = writeln!(yaml, "base{:02X}: \"{}\"", i, base);
+    }
+    yaml
+}
@@ -0,0 +1,217 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Single file `.cosmic-theme` bundles for sharing themes.
+//! A bundle is a zip archive holding a RON manifest with the selection,
+//! constraints and derived theme, a preview image and optionally the wallpaper
+//! the theme was made from.
+
+use super::preview_png;
+use crate::{
+    config::{APP_ID, VERSION},
+    metadata::{ThemeMetadata, ThemeVariant},
+    project::ThemeProject,
+    syntax::SyntaxPalette,
+    util::{now_iso8601, SRGBA},
+};
+use anyhow::{anyhow, bail};
+use cosmic_theme::{Selection, Theme, ThemeConstraints};
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Read, Write};
+use zip::{write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+pub const BUNDLE_EXTENSION: &str = "cosmic-theme";
+
+const FORMAT_VERSION: u32 = 1;
+const MANIFEST: &str = "theme.ron";
+const PREVIEW: &str = "preview.png";
+const WALLPAPER: &str = "wallpaper";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ThemeBundle {
+    pub format_version: u32,
+    /// application and version that wrote the bundle
+    pub generator: String,
+    /// ISO 8601 time the bundle was written
+    pub exported: String,
+    pub selection: Selection<SRGBA>,
+    pub constraints: ThemeConstraints,
+    pub theme: Theme<SRGBA>,
+    #[serde(default)]
+    pub metadata: ThemeMetadata,
+    /// both variants of the theme, when it was made as a light and dark pair
+    #[serde(default)]
+    pub project: ThemeProject,
+    /// the variant of the project that was being edited,
+    /// bundles written before it was stored guess it from the theme
+    #[serde(default)]
+    pub variant: Option<ThemeVariant>,
+    /// syntax highlighting colors, when the user changed the derived ones
+    #[serde(default)]
+    pub syntax: Option<SyntaxPalette>,
+    #[serde(skip)]
+    pub wallpaper: Option<Wallpaper>,
+}
+
+#[derive(Debug)]
+pub struct Wallpaper {
+    /// file extension of the image, such as `png` or `jpg`
+    pub extension: String,
+    pub data: Vec<u8>,
+}
+
+impl Wallpaper {
+    /// A file name for the wallpaper of the theme `theme_name`, which is safe to
+    /// join to a directory whatever the bundle holds
+    pub fn file_name(&self, theme_name: &str) -> String {
+        let mut name: String = theme_name
+            .chars()
+            .map(|c| match c {
+                c if c.is_alphanumeric() || c == '-' || c == '_' => c,
+                _ => '_',
+            })
+            .collect();
+        if name.trim_matches('_').is_empty() {
+            name = WALLPAPER.to_string();
+        }
+        format!("{}.{}", name, self.extension)
+    }
+}
+
+impl ThemeBundle {
+    pub fn new(
+        selection: Selection<SRGBA>,
+        constraints: ThemeConstraints,
+        theme: Theme<SRGBA>,
+        metadata: ThemeMetadata,
+        project: ThemeProject,
+        syntax: Option<SyntaxPalette>,
+        wallpaper: Option<Wallpaper>,
+    ) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            generator: format!("{} {}", APP_ID, VERSION),
+            exported: now_iso8601(),
+            selection,
+            constraints,
+            theme,
+            metadata,
+            project,
+            variant: None,
+            syntax,
+            wallpaper,
+        }
+    }
+
+    pub fn file_name(&self) -> String {
+        format!("{}.{}", self.theme.name, BUNDLE_EXTENSION)
+    }
+
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = FileOptions::default();
+        // images are already compressed
+        let stored = options.compression_method(CompressionMethod::Stored);
+
+        zip.start_file(MANIFEST, options)?;
+        zip.write_all(ron::ser::to_string_pretty(self, PrettyConfig::new())?.as_bytes())?;
+
+        zip.start_file(PREVIEW, stored)?;
+        zip.write_all(&preview_png(&self.theme, 512, 320)?)?;
+
+        if let Some(wallpaper) = &self.wallpaper {
+            zip.start_file(format!("{}.{}", WALLPAPER, wallpaper.extension), stored)?;
+            zip.write_all(&wallpaper.data)?;
+        }
+        Ok(zip.finish()?.into_inner())
+    }
+
+    pub fn from_bytes(data: &[u8]) -> anyhow::Result<Self> {
+        let mut zip =
+            ZipArchive::new(Cursor::new(data)).map_err(|_| anyhow!("not a COSMIC theme bundle"))?;
+
+        let mut manifest = String::new();
+        zip.by_name(MANIFEST)?.read_to_string(&mut manifest)?;
+        let mut bundle: Self = ron::from_str(&manifest)?;
+        if bundle.format_version > FORMAT_VERSION {
+            bail!(
+                "the theme was made with a newer version of the editor ({})",
+                bundle.generator
+            );
+        }
+
+        let wallpaper_name = zip
+            .file_names()
+            .find(|name| name.starts_with(&format!("{}.", WALLPAPER)))
+            .map(str::to_string);
+        if let Some(name) = wallpaper_name {
+            let extension = &name[WALLPAPER.len() + 1..];
+            if extension.is_empty()
+                || extension.len() > 5
+                || !extension.chars().all(|c| c.is_ascii_alphanumeric())
+            {
+                bail!("the wallpaper in the bundle has an invalid name: {}", name);
+            }
+            let mut data = Vec::new();
+            zip.by_name(&name)?.read_to_end(&mut data)?;
+            bundle.wallpaper = Some(Wallpaper {
+                extension: extension.to_string(),
+                data,
+            });
+        }
+        Ok(bundle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::test_selection;
+    use cosmic_theme::{ColorPicker, Exact};
+
+    fn bundle_with_entry(theme_name: &str, entry: &str) -> Vec<u8> {
+        let selection = test_selection();
+        let constraints = ThemeConstraints::default();
+        let mut theme = Exact::new(selection, constraints)
+            .theme_derivation()
+            .derived;
+        theme.name = theme_name.to_string();
+        let bundle = ThemeBundle::new(
+            selection,
+            constraints,
+            theme,
+            ThemeMetadata::default(),
+            ThemeProject::default(),
+            None,
+            None,
+        );
+
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file(MANIFEST, FileOptions::default()).unwrap();
+        zip.write_all(
+            ron::ser::to_string_pretty(&bundle, PrettyConfig::new())
+                .unwrap()
+                .as_bytes(),
+        )
+        .unwrap();
+        zip.start_file(entry, FileOptions::default()).unwrap();
+        zip.write_all(b"not an image").unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn wallpaper_extension_with_path_is_rejected() {
+        let data = bundle_with_entry("", "wallpaper./../../../.config/autostart/x.desktop");
+        assert!(ThemeBundle::from_bytes(&data).is_err());
+    }
+
+    #[test]
+    fn wallpaper_file_name_stays_in_its_directory() {
+        let data = bundle_with_entry("../../x", "wallpaper.png");
+        let bundle = ThemeBundle::from_bytes(&data).unwrap();
+        let wallpaper = bundle.wallpaper.unwrap();
+        assert_eq!(wallpaper.file_name(&bundle.theme.name), "______x.png");
+        assert_eq!(wallpaper.file_name(""), "wallpaper.png");
+        assert_eq!(wallpaper.file_name(".."), "wallpaper.png");
+    }
+}
@@ -0,0 +1,423 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::{
+    syntax::SyntaxPalette,
+    util::{hex_from_rgba, is_dark, mix, over, rgba_from_hex, SRGBA},
+};
+use anyhow::anyhow;
+use cosmic_theme::{Selection, Theme};
+use serde_json::{json, Map, Value};
+use std::fmt::Write;
+
+/// `#rrggbb` of a color flattened onto the surface it is drawn on
+fn solid(color: &SRGBA, surface: &SRGBA) -> String {
+    let hex = hex_from_rgba(&over(color, surface));
+    hex[..7].to_string()
+}
+
+/// A Lua colorscheme for `~/.config/nvim/colors/`
+pub fn neovim_colorscheme(theme: &Theme<SRGBA>, syntax: &SyntaxPalette) -> String {
+    let bg = &theme.background;
+    let primary = &theme.primary;
+    let secondary = &theme.secondary;
+    let suggested = &theme.accent.suggested;
+    let surface = &bg.container;
+    let c = |color: &SRGBA| solid(color, surface);
+
+    let groups = [
+        (
+            "Normal",
+            Some(c(&bg.container_text)),
+            Some(c(&bg.container)),
+        ),
+        (
+            "NormalFloat",
+            Some(c(&secondary.container_text)),
+            Some(c(&secondary.container)),
+        ),
+        (
+            "FloatBorder",
+            Some(c(&secondary.container_divider)),
+            Some(c(&secondary.container)),
+        ),
+        (
+            "Pmenu",
+            Some(c(&primary.container_text)),
+            Some(c(&primary.container)),
+        ),
+        (
+            "PmenuSel",
+            Some(c(&suggested.text)),
+            Some(c(&suggested.default)),
+        ),
+        (
+            "PmenuSbar",
+            None,
+            Some(c(&primary.container_component.default)),
+        ),
+        (
+            "PmenuThumb",
+            None,
+            Some(c(&primary.container_component.pressed)),
+        ),
+        ("CursorLine", None, Some(c(&bg.container_component.default))),
+        (
+            "ColorColumn",
+            None,
+            Some(c(&bg.container_component.default)),
+        ),
+        ("Visual", None, Some(c(&bg.container_component.pressed))),
+        ("LineNr", Some(c(&bg.container_text_opacity_80)), None),
+        ("CursorLineNr", Some(c(&theme.accent.accent)), None),
+        ("SignColumn", None, Some(c(&bg.container))),
+        ("WinSeparator", Some(c(&bg.container_divider)), None),
+        ("VertSplit", Some(c(&bg.container_divider)), None),
+        (
+            "StatusLine",
+            Some(c(&primary.container_component.text)),
+            Some(c(&primary.container_component.default)),
+        ),
+        (
+            "StatusLineNC",
+            Some(c(&bg.container_component.text_opacity_80)),
+            Some(c(&bg.container_component.default)),
+        ),
+        (
+            "TabLine",
+            Some(c(&bg.container_component.text_opacity_80)),
+            Some(c(&bg.container_component.default)),
+        ),
+        (
+            "TabLineSel",
+            Some(c(&primary.container_text)),
+            Some(c(&primary.container)),
+        ),
+        ("TabLineFill", None, Some(c(&bg.container))),
+        (
+            "Search",
+            Some(c(&suggested.text)),
+            Some(c(&suggested.default)),
+        ),
+        (
+            "IncSearch",
+            Some(c(&suggested.text)),
+            Some(c(&suggested.pressed)),
+        ),
+        (
+            "MatchParen",
+            Some(c(&theme.accent.accent)),
+            Some(c(&bg.container_component.pressed)),
+        ),
+        ("Title", Some(c(&theme.accent.accent)), None),
+        ("Directory", Some(c(&theme.accent.accent)), None),
+        ("NonText", Some(c(&bg.container_divider)), None),
+        ("Comment", Some(c(&syntax.comment)), None),
+        ("Keyword", Some(c(&syntax.keyword)), None),
+        ("Statement", Some(c(&syntax.keyword)), None),
+        ("String", Some(c(&syntax.string)), None),
+        ("Character", Some(c(&syntax.string)), None),
+        ("Type", Some(c(&syntax.type_)), None),
+        ("Function", Some(c(&syntax.function)), None),
+        ("Identifier", Some(c(&bg.container_text)), None),
+        ("Constant", Some(c(&syntax.number)), None),
+        ("Number", Some(c(&syntax.number)), None),
+        ("Boolean", Some(c(&syntax.number)), None),
+        ("Error", Some(c(&syntax.error)), None),
+        ("ErrorMsg", Some(c(&syntax.error)), None),
+        ("WarningMsg", Some(c(&syntax.type_)), None),
+        ("DiagnosticError", Some(c(&syntax.error)), None),
+        ("DiagnosticWarn", Some(c(&syntax.type_)), None),
+        ("DiagnosticInfo", Some(c(&theme.accent.accent)), None),
+        ("DiagnosticHint", Some(c(&syntax.comment)), None),
+    ];
+
+    let mut lua = String::new();
+    let _ = writeln!(
+        lua,
+        "-- {} colorscheme, generated by {}",
+        theme.name,
+        crate::APP_TITLE
+    );
+    lua.push_str("vim.cmd('highlight clear')\n");
+    lua.push_str("if vim.fn.exists('syntax_on') == 1 then\n  vim.cmd('syntax reset')\nend\n");
+    lua.push_str("vim.o.termguicolors = true\n");
+    let _ = writeln!(
+        lua,
+        "vim.o.background = '{}'",
+        if is_dark(&theme.background.container) {
+            "dark"
+        } else {
+            "light"
+        }
+    );
+    let _ = writeln!(lua, "vim.g.colors_name = {:?}\n", theme.name);
+    lua.push_str("local hl = function(group, opts) vim.api.nvim_set_hl(0, group, opts) end\n\n");
+
+    for (group, fg, bg) in groups {
+        let opts = [
+            fg.map(|fg| format!("fg = '{}'", fg)),
+            bg.map(|bg| format!("bg = '{}'", bg)),
+        ];
+        let opts: Vec<String> = opts.into_iter().flatten().collect();
+        let _ = writeln!(lua, "hl('{}', {{ {} }})", group, opts.join(", "));
+    }
+    lua
+}
+
+/// A VS Code color theme, as contributed by an extension's `themes` entry
+pub fn vscode_theme(theme: &Theme<SRGBA>, syntax: &SyntaxPalette) -> String {
+    let bg = &theme.background;
+    let primary = &theme.primary;
+    let secondary = &theme.secondary;
+    let suggested = &theme.accent.suggested;
+    let destructive = &theme.destructive.destructive;
+    let c = |color: &SRGBA| hex_from_rgba(color);
+
+    let colors: Map<String, Value> = [
+        ("foreground", c(&bg.container_text)),
+        ("focusBorder", c(&theme.accent.accent)),
+        ("errorForeground", c(&syntax.error)),
+        ("editor.background", c(&bg.container)),
+        ("editor.foreground", c(&bg.container_text)),
+        (
+            "editor.lineHighlightBackground",
+            c(&bg.container_component.default),
+        ),
+        (
+            "editor.selectionBackground",
+            c(&bg.container_component.pressed),
+        ),
+        (
+            "editorLineNumber.foreground",
+            c(&bg.container_text_opacity_80),
+        ),
+        ("editorLineNumber.activeForeground", c(&theme.accent.accent)),
+        ("editorCursor.foreground", c(&theme.accent.accent)),
+        ("editorError.foreground", c(&syntax.error)),
+        ("editorWidget.background", c(&secondary.container)),
+        ("editorWidget.foreground", c(&secondary.container_text)),
+        ("editorGroup.border", c(&bg.container_divider)),
+        ("editorGroupHeader.tabsBackground", c(&primary.container)),
+        ("tab.activeBackground", c(&bg.container)),
+        ("tab.activeForeground", c(&bg.container_text)),
+        ("tab.inactiveBackground", c(&primary.container)),
+        (
+            "tab.inactiveForeground",
+            c(&primary.container_text_opacity_80),
+        ),
+        ("tab.border", c(&primary.container_divider)),
+        ("sideBar.background", c(&primary.container)),
+        ("sideBar.foreground", c(&primary.container_text)),
+        ("sideBar.border", c(&primary.container_divider)),
+        ("activityBar.background", c(&primary.container)),
+        ("activityBar.foreground", c(&primary.container_text)),
+        ("activityBarBadge.background", c(&suggested.default)),
+        ("activityBarBadge.foreground", c(&suggested.text)),
+        (
+            "titleBar.activeBackground",
+            c(&bg.container_component.default),
+        ),
+        ("titleBar.activeForeground", c(&bg.container_component.text)),
+        (
+            "statusBar.background",
+            c(&primary.container_component.default),
+        ),
+        ("statusBar.foreground", c(&primary.container_component.text)),
+        ("panel.background", c(&primary.container)),
+        ("panel.border", c(&primary.container_divider)),
+        ("list.activeSelectionBackground", c(&suggested.default)),
+        ("list.activeSelectionForeground", c(&suggested.text)),
+        (
+            "list.hoverBackground",
+            c(&primary.container_component.hover),
+        ),
+        ("button.background", c(&suggested.default)),
+        ("button.foreground", c(&suggested.text)),
+        ("button.hoverBackground", c(&suggested.hover)),
+        (
+            "input.background",
+            c(&secondary.container_component.default),
+        ),
+        ("input.foreground", c(&secondary.container_component.text)),
+        ("dropdown.background", c(&secondary.container)),
+        ("dropdown.foreground", c(&secondary.container_text)),
+        ("inputValidation.errorBorder", c(&destructive.default)),
+    ]
+    .into_iter()
+    .map(|(key, color)| (key.to_string(), Value::String(color)))
+    .collect();
+
+    let token = |scope: &[&str], color: &SRGBA| json!({ "scope": scope, "settings": { "foreground": c(color) } });
+    let token_colors = vec![
+        json!({ "scope": ["comment"], "settings": { "foreground": c(&syntax.comment), "fontStyle": "italic" } }),
+        token(&["keyword", "storage"], &syntax.keyword),
+        token(&["string"], &syntax.string),
+        token(&["entity.name.type", "support.type"], &syntax.type_),
+        token(
+            &["entity.name.function", "support.function"],
+            &syntax.function,
+        ),
+        token(&["constant.numeric", "constant.language"], &syntax.number),
+        token(&["invalid"], &syntax.error),
+    ];
+
+    let vscode = json!({
+        "name": theme.name,
+        "type": if is_dark(&theme.background.container) { "dark" } else { "light" },
+        "colors": colors,
+        "tokenColors": token_colors,
+    });
+    serde_json::to_string_pretty(&vscode).unwrap()
+}
+
+/// Import a VS Code color theme. The editor surfaces become the containers,
+/// falling back to steps from the editor background towards its foreground.
+pub fn selection_from_vscode(json: &str) -> anyhow::Result<Selection<SRGBA>> {
+    let theme: Value = serde_json::from_str(&strip_json_comments(json))?;
+    let colors = theme
+        .get("colors")
+        .and_then(Value::as_object)
+        .ok_or_else(|| anyhow!("not a VS Code color theme"))?;
+    let color = |keys: &[&str]| {
+        keys.iter()
+            .filter_map(|key| colors.get(*key).and_then(Value::as_str))
+            .find_map(|c| rgba_from_hex(c).ok())
+    };
+
+    let background =
+        color(&["editor.background"]).ok_or_else(|| anyhow!("missing editor.background"))?;
+    let foreground = color(&["editor.foreground", "foreground"])
+        .ok_or_else(|| anyhow!("missing editor.foreground"))?;
+
+    Ok(Selection {
+        background,
+        primary_container: color(&["sideBar.background", "activityBar.background"])
+            .unwrap_or_else(|| mix(&background, &foreground, 0.08)),
+        secondary_container: color(&["editorWidget.background", "dropdown.background"])
+            .unwrap_or_else(|| mix(&background, &foreground, 0.16)),
+        accent: color(&[
+            "button.background",
+            "focusBorder",
+            "activityBarBadge.background",
+            "terminal.ansiBlue",
+        ])
+        .ok_or_else(|| anyhow!("missing button.background"))?,
+        destructive: color(&[
+            "editorError.foreground",
+            "errorForeground",
+            "terminal.ansiRed",
+        ])
+        .ok_or_else(|| anyhow!("missing editorError.foreground"))?,
+        accent_text: color(&["textLink.foreground", "terminal.ansiBrightBlue"]),
+        ..Default::default()
+    })
+}
+
+/// VS Code themes are JSON with comments and trailing commas.
+/// Comments go first, so a comma followed by a comment and a bracket is seen as trailing.
+fn strip_json_comments(json: &str) -> String {
+    strip_trailing_commas(&strip_comments(json))
+}
+
+fn strip_comments(json: &str) -> String {
+    let mut stripped = String::with_capacity(json.len());
+    let mut chars = json.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            stripped.push(c);
+            match c {
+                '\\' => stripped.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                stripped.push(c);
+            }
+            ('/', Some('/')) => {
+                while chars.peek().map_or(false, |c| *c != '\n') {
+                    chars.next();
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                while let Some(c) = chars.next() {
+                    if c == '*' && chars.peek() == Some(&'/') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            _ => stripped.push(c),
+        }
+    }
+    stripped
+}
+
+fn strip_trailing_commas(json: &str) -> String {
+    let mut stripped = String::with_capacity(json.len());
+    let mut chars = json.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            stripped.push(c);
+            match c {
+                '\\' => stripped.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_string = true;
+                stripped.push(c);
+            }
+            ',' => {
+                let rest = chars.clone().find(|c| !c.is_whitespace());
+                if !matches!(rest, Some('}') | Some(']')) {
+                    stripped.push(c);
+                }
+            }
+            _ => stripped.push(c),
+        }
+    }
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comments_before_closing_brackets() {
+        let json = "{\"a\": 1, // c\n}";
+        assert_eq!(
+            serde_json::from_str::<Value>(&strip_json_comments(json)).unwrap(),
+            json!({ "a": 1 })
+        );
+
+        let json = "{\n  \"colors\": [\"#fff\", /* last */\n  ],\n  // done\n}";
+        assert_eq!(
+            serde_json::from_str::<Value>(&strip_json_comments(json)).unwrap(),
+            json!({ "colors": ["#fff"] })
+        );
+    }
+
+    #[test]
+    fn strings_are_kept() {
+        let json = r#"{"url": "https://example.com", "s": "a, }", "q": "\"//"}"#;
+        assert_eq!(
+            serde_json::from_str::<Value>(&strip_json_comments(json)).unwrap(),
+            json!({ "url": "https://example.com", "s": "a, }", "q": "\"//" })
+        );
+    }
+}
@@ -0,0 +1,357 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::util::{css_rgba, mix, rgba_from_hex, SRGBA};
+use anyhow::{anyhow, bail};
+use cosmic_theme::{Selection, Theme};
+use palette::{Clamp, Hsl, IntoColor, Srgba};
+use std::{collections::HashMap, fmt::Write};
+
+/// Named colors understood by GTK 3 themes and applications
+pub trait Gtk3Output {
+    fn as_gtk3_css(&self) -> String;
+}
+
+/// Named colors understood by libadwaita, for `~/.config/gtk-4.0/gtk.css`
+pub trait AdwaitaOutput {
+    fn as_adwaita_css(&self) -> String;
+}
+
+impl Gtk3Output for Theme<SRGBA> {
+    fn as_gtk3_css(&self) -> String {
+        let bg = &self.background;
+        let primary = &self.primary;
+        let accent = &self.accent;
+        let destructive = &self.destructive.destructive;
+
+        define_colors(&[
+            ("theme_bg_color", bg.container),
+            ("theme_fg_color", bg.container_text),
+            ("theme_base_color", primary.container),
+            ("theme_text_color", primary.container_text),
+            ("theme_selected_bg_color", accent.accent),
+            ("theme_selected_fg_color", accent.accent_text),
+            ("theme_unfocused_bg_color", bg.container),
+            ("theme_unfocused_fg_color", bg.container_text_opacity_80),
+            ("theme_unfocused_base_color", primary.container),
+            (
+                "theme_unfocused_text_color",
+                primary.container_text_opacity_80,
+            ),
+            ("theme_unfocused_selected_bg_color", accent.accent),
+            ("theme_unfocused_selected_fg_color", accent.accent_text),
+            ("insensitive_bg_color", bg.container_component.disabled),
+            ("insensitive_fg_color", bg.container_component.disabled_text),
+            (
+                "insensitive_base_color",
+                primary.container_component.disabled,
+            ),
+            ("borders", bg.container_divider),
+            ("unfocused_borders", bg.container_divider),
+            ("error_color", destructive.default),
+            ("error_bg_color", destructive.default),
+            ("error_fg_color", destructive.text),
+            ("destructive_color", destructive.default),
+        ])
+    }
+}
+
+impl AdwaitaOutput for Theme<SRGBA> {
+    fn as_adwaita_css(&self) -> String {
+        let bg = &self.background;
+        let primary = &self.primary;
+        let secondary = &self.secondary;
+        let suggested = &self.accent.suggested;
+        let destructive = &self.destructive.destructive;
+
+        define_colors(&[
+            ("accent_color", self.accent.accent),
+            ("accent_bg_color", suggested.default),
+            ("accent_fg_color", suggested.text),
+            ("destructive_color", destructive.default),
+            ("destructive_bg_color", destructive.default),
+            ("destructive_fg_color", destructive.text),
+            ("error_color", destructive.default),
+            ("error_bg_color", destructive.default),
+            ("error_fg_color", destructive.text),
+            ("window_bg_color", bg.container),
+            ("window_fg_color", bg.container_text),
+            ("view_bg_color", primary.container),
+            ("view_fg_color", primary.container_text),
+            ("headerbar_bg_color", bg.container_component.default),
+            ("headerbar_fg_color", bg.container_component.text),
+            ("headerbar_border_color", bg.container_divider),
+            ("headerbar_backdrop_color", bg.container),
+            ("card_bg_color", primary.container_component.default),
+            ("card_fg_color", primary.container_component.text),
+            ("popover_bg_color", secondary.container),
+            ("popover_fg_color", secondary.container_text),
+            ("dialog_bg_color", secondary.container),
+            ("dialog_fg_color", secondary.container_text),
+            ("borders", bg.container_divider),
+        ])
+    }
+}
+
+fn define_colors(colors: &[(&str, SRGBA)]) -> String {
+    colors.iter().fold(String::new(), |mut css, (name, color)| {
+        let _ = writeln!(css, "@define-color {} {};", name, css_rgba(color));
+        css
+    })
+}
+
+/// Import the `@define-color` declarations of a GTK 3 or GTK 4 theme,
+/// such as `/usr/share/themes/<theme>/gtk-3.0/gtk.css`. Both the classic
+/// GTK names and the libadwaita names are understood. Colors defined in
+/// files pulled in with `@import` are not followed.
+pub fn selection_from_gtk_css(css: &str) -> anyhow::Result<Selection<SRGBA>> {
+    let css = strip_css_comments(css);
+    let defines = color_defines(&css);
+    let color = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| resolve_gtk_color(&format!("@{}", name), &defines, 0).ok())
+    };
+
+    let background = color(&["theme_bg_color", "window_bg_color", "bg_color"])
+        .ok_or_else(|| anyhow!("missing theme_bg_color"))?;
+    let foreground = color(&["theme_fg_color", "window_fg_color", "fg_color"])
+        .ok_or_else(|| anyhow!("missing theme_fg_color"))?;
+
+    Ok(Selection {
+        background,
+        primary_container: color(&["theme_base_color", "view_bg_color", "base_color"])
+            .unwrap_or_else(|| mix(&background, &foreground, 0.08)),
+        secondary_container: color(&["headerbar_bg_color", "popover_bg_color", "card_bg_color"])
+            .unwrap_or_else(|| mix(&background, &foreground, 0.16)),
+        accent: color(&[
+            "theme_selected_bg_color",
+            "accent_bg_color",
+            "selected_bg_color",
+        ])
+        .ok_or_else(|| anyhow!("missing theme_selected_bg_color"))?,
+        destructive: color(&["error_color", "destructive_color", "error_bg_color"])
+            .ok_or_else(|| anyhow!("missing error_color"))?,
+        accent_text: color(&["accent_color", "link_color"]),
+        ..Default::default()
+    })
+}
+
+/// The name and value of every `@define-color`.
+/// A statement can start with the end of a rule block, so the keyword is looked for anywhere in it.
+fn color_defines(css: &str) -> HashMap<&str, &str> {
+    const DEFINE: &str = "@define-color";
+    css.split(';')
+        .filter_map(|statement| {
+            statement
+                .find(DEFINE)
+                .map(|i| &statement[i + DEFINE.len()..])
+        })
+        .filter_map(|define| define.trim().split_once(char::is_whitespace))
+        .map(|(name, value)| (name, value.trim()))
+        .collect()
+}
+
+fn strip_css_comments(css: &str) -> String {
+    let mut stripped = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        stripped.push_str(&rest[..start]);
+        rest = rest[start + 2..]
+            .find("*/")
+            .map_or("", |end| &rest[start + end + 4..]);
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+/// Evaluate a GTK color expression, following references to other defined colors
+fn resolve_gtk_color(
+    expr: &str,
+    defines: &HashMap<&str, &str>,
+    depth: usize,
+) -> anyhow::Result<SRGBA> {
+    if depth > 32 {
+        bail!("color references are too deep: {}", expr);
+    }
+    let expr = expr.trim();
+    let resolve = |expr: &str| resolve_gtk_color(expr, defines, depth + 1);
+    let number = |n: &str| -> anyhow::Result<f32> {
+        let n = n.trim();
+        match n.strip_suffix('%') {
+            Some(percent) => Ok(percent.trim().parse::<f32>()? / 100.0),
+            None => Ok(n.parse()?),
+        }
+    };
+
+    if let Some(name) = expr.strip_prefix('@') {
+        let value = defines
+            .get(name)
+            .ok_or_else(|| anyhow!("undefined color @{}", name))?;
+        return resolve(value);
+    }
+    if expr.starts_with('#') {
+        return rgba_from_hex(expr);
+    }
+
+    let (function, args) = match expr.strip_suffix(')').and_then(|expr| expr.split_once('(')) {
+        Some((function, args)) => (function.trim(), split_args(args)),
+        None => {
+            return match expr {
+                "transparent" => Ok(SRGBA(Srgba::new(0.0, 0.0, 0.0, 0.0))),
+                "white" => Ok(SRGBA(Srgba::new(1.0, 1.0, 1.0, 1.0))),
+                "black" => Ok(SRGBA(Srgba::new(0.0, 0.0, 0.0, 1.0))),
+                _ => Err(anyhow!("unsupported color: {}", expr)),
+            }
+        }
+    };
+
+    match (function, args.as_slice()) {
+        ("rgb", [r, g, b]) | ("rgba", [r, g, b]) => rgba(r, g, b, "1", number),
+        ("rgb", [r, g, b, a]) | ("rgba", [r, g, b, a]) => rgba(r, g, b, a, number),
+        ("alpha", [c, a]) => {
+            let mut c = resolve(c)?;
+            c.alpha = (c.alpha * number(a)?).clamp(0.0, 1.0);
+            Ok(c)
+        }
+        ("mix", [a, b, t]) => {
+            let (a, b) = (resolve(a)?, resolve(b)?);
+            Ok(mix(&a, &b, number(t)?))
+        }
+        ("shade", [c, f]) => Ok(shade(resolve(c)?, number(f)?)),
+        ("lighter", [c]) => Ok(shade(resolve(c)?, 1.3)),
+        ("darker", [c]) => Ok(shade(resolve(c)?, 0.7)),
+        _ => Err(anyhow!("unsupported color: {}", expr)),
+    }
+}
+
+fn rgba(
+    r: &str,
+    g: &str,
+    b: &str,
+    a: &str,
+    number: impl Fn(&str) -> anyhow::Result<f32>,
+) -> anyhow::Result<SRGBA> {
+    // Percentages are already fractions, plain channels are out of 255
+    let channel = |c: &str| -> anyhow::Result<f32> {
+        let n = number(c)?;
+        Ok(if c.trim().ends_with('%') {
+            n
+        } else {
+            n / 255.0
+        })
+    };
+    Ok(SRGBA(
+        Srgba::new(channel(r)?, channel(g)?, channel(b)?, number(a)?).clamp(),
+    ))
+}
+
+/// GTK's `shade()` scales lightness and saturation
+fn shade(c: SRGBA, factor: f32) -> SRGBA {
+    let mut hsl: Hsl = c.color.into_color();
+    hsl.lightness *= factor;
+    hsl.saturation *= factor;
+    let rgb: palette::Srgb = hsl.clamp().into_color();
+    SRGBA(Srgba::from_components((
+        rgb.red, rgb.green, rgb.blue, c.alpha,
+    )))
+}
+
+/// Split function arguments on the commas that are not nested in parentheses
+fn split_args(args: &str) -> Vec<&str> {
+    let mut split = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in args.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                split.push(&args[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    split.push(&args[start..]);
+    split
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::hex_from_rgba;
+
+    const CSS: &str = "
+        /* named colors, with a rule in between */
+        @define-color theme_bg_color #202020;
+        window { background-color: @theme_bg_color; }
+        @define-color theme_fg_color #eeeeee;
+        .view {
+            color: @theme_fg_color;
+        }
+        @define-color accent_bg_color @blue;
+        @define-color blue rgb(53, 132, 228);
+        @define-color faded alpha(@accent_bg_color, 0.5);
+        @define-color loop_a @loop_b;
+        @define-color loop_b @loop_a;
+    ";
+
+    fn resolve(expr: &str) -> anyhow::Result<SRGBA> {
+        resolve_gtk_color(expr, &color_defines(CSS), 0)
+    }
+
+    #[test]
+    fn defines_after_rules() {
+        let defines = color_defines(CSS);
+        assert_eq!(defines.get("theme_bg_color"), Some(&"#202020"));
+        assert_eq!(defines.get("theme_fg_color"), Some(&"#eeeeee"));
+    }
+
+    #[test]
+    fn references() {
+        // rgb() channels are out of 255, so compare the rounded colors
+        assert_eq!(
+            hex_from_rgba(&resolve("@accent_bg_color").unwrap()),
+            "#3584e4ff"
+        );
+        let faded = resolve("@faded").unwrap();
+        assert_eq!(faded.alpha, 0.5);
+        assert_eq!(faded.red, resolve("@blue").unwrap().red);
+    }
+
+    #[test]
+    fn undefined_and_cyclic_references() {
+        assert!(resolve("@missing").is_err());
+        assert!(resolve("@loop_a").is_err());
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(
+            resolve("mix(#000000, #ffffff, 0.5)").unwrap(),
+            mix(
+                &rgba_from_hex("#000000").unwrap(),
+                &rgba_from_hex("#ffffff").unwrap(),
+                0.5
+            )
+        );
+        assert_eq!(
+            resolve("rgba(100%, 0%, 0%, 0.25)").unwrap(),
+            SRGBA(Srgba::new(1.0, 0.0, 0.0, 0.25))
+        );
+        assert!(
+            resolve("shade(@theme_bg_color, 1.2)").unwrap().red
+                > resolve("@theme_bg_color").unwrap().red
+        );
+        assert!(resolve("blur(@theme_bg_color)").is_err());
+    }
+
+    #[test]
+    fn selection_from_adwaita_like_css() {
+        let css = format!("{}\n@define-color error_color #e01b24;", CSS);
+        let selection = selection_from_gtk_css(&css).unwrap();
+        assert_eq!(selection.background, rgba_from_hex("#202020").unwrap());
+        assert_eq!(hex_from_rgba(&selection.accent), "#3584e4ff");
+        assert_eq!(selection.destructive, rgba_from_hex("#e01b24").unwrap());
+    }
+}
@@ -0,0 +1,112 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use super::{AdwaitaOutput, Gtk3Output};
+use crate::util::SRGBA;
+use anyhow::{anyhow, Context};
+use cosmic_theme::Theme;
+use gtk4::glib;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+
+const MANIFEST: &str = "manifest.ron";
+
+/// Record of the files written by [`install_gtk_theme`], used to revert them
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GtkInstallManifest {
+    pub theme: String,
+    pub files: Vec<InstalledFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InstalledFile {
+    pub target: PathBuf,
+    /// copy of the user's original file, if there was one
+    pub backup: Option<PathBuf>,
+}
+
+fn backup_dir() -> PathBuf {
+    glib::user_data_dir()
+        .join("cosmic-theme-editor")
+        .join("gtk-backup")
+}
+
+pub fn read_gtk_install_manifest() -> anyhow::Result<Option<GtkInstallManifest>> {
+    let path = backup_dir().join(MANIFEST);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let manifest = fs::read_to_string(&path)?;
+    Ok(Some(ron::from_str(&manifest)?))
+}
+
+/// Write the theme into the user's `gtk-3.0` and `gtk-4.0` config directories.
+/// Existing `gtk.css` files are backed up the first time a theme is applied,
+/// so applying several themes in a row still reverts to the user's original files.
+pub fn install_gtk_theme(theme: &Theme<SRGBA>) -> anyhow::Result<()> {
+    let backup_dir = backup_dir();
+    fs::create_dir_all(&backup_dir)?;
+
+    let mut manifest = read_gtk_install_manifest()?.unwrap_or_default();
+    let config_dir = glib::user_config_dir();
+    let outputs = [
+        ("gtk-3.0", theme.as_gtk3_css()),
+        ("gtk-4.0", theme.as_adwaita_css()),
+    ];
+
+    for (version, _) in &outputs {
+        let target = config_dir.join(version).join("gtk.css");
+        if manifest.files.iter().any(|f| f.target == target) {
+            continue;
+        }
+
+        let backup = if target.exists() {
+            let backup = backup_dir.join(format!("{}.css", version));
+            fs::copy(&target, &backup)
+                .with_context(|| format!("failed to back up {}", target.display()))?;
+            Some(backup)
+        } else {
+            None
+        };
+        manifest.files.push(InstalledFile { target, backup });
+    }
+
+    manifest.theme = theme.name.clone();
+    fs::write(
+        backup_dir.join(MANIFEST),
+        ron::ser::to_string_pretty(&manifest, PrettyConfig::new())?,
+    )?;
+
+    for (version, css) in outputs {
+        let target_dir = config_dir.join(version);
+        fs::create_dir_all(&target_dir)?;
+        fs::write(target_dir.join("gtk.css"), css)?;
+    }
+    Ok(())
+}
+
+/// Restore the files backed up by [`install_gtk_theme`]
+pub fn revert_gtk_theme() -> anyhow::Result<()> {
+    let manifest = read_gtk_install_manifest()?
+        .ok_or_else(|| anyhow!("No theme has been applied to GTK apps"))?;
+
+    for file in &manifest.files {
+        match &file.backup {
+            Some(backup) => {
+                fs::copy(backup, &file.target)
+                    .with_context(|| format!("failed to restore {}", file.target.display()))?;
+            }
+            None if file.target.exists() => fs::remove_file(&file.target)?,
+            None => {}
+        }
+    }
+
+    // the backups are kept until every file is restored, so a failed revert can be retried
+    for file in manifest.files {
+        if let Some(backup) = file.backup {
+            fs::remove_file(backup)?;
+        }
+    }
+    fs::remove_file(backup_dir().join(MANIFEST))?;
+    Ok(())
+}
@@ -0,0 +1,67 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Import from KDE color schemes
+
+use crate::util::{rgba_from_hex, SRGBA};
+use anyhow::{anyhow, bail};
+use cosmic_theme::Selection;
+use palette::Srgba;
+use std::collections::HashMap;
+
+/// KDE `.colors` scheme, such as those in `/usr/share/color-schemes`
+pub fn selection_from_kde_colors(colors: &str) -> anyhow::Result<Selection<SRGBA>> {
+    let mut entries = HashMap::new();
+    let mut group = "";
+    for line in colors.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            group = name;
+        } else if let Some((key, value)) = line.split_once('=') {
+            entries.insert(format!("{}/{}", group, key.trim()), value.trim());
+        }
+    }
+    let color = |keys: &[&str]| {
+        keys.iter()
+            .filter_map(|key| entries.get(*key))
+            .find_map(|value| kde_color(value).ok())
+    };
+
+    Ok(Selection {
+        background: color(&["Colors:Window/BackgroundNormal"])
+            .ok_or_else(|| anyhow!("missing Colors:Window/BackgroundNormal"))?,
+        primary_container: color(&["Colors:View/BackgroundNormal"])
+            .ok_or_else(|| anyhow!("missing Colors:View/BackgroundNormal"))?,
+        secondary_container: color(&[
+            "Colors:Button/BackgroundNormal",
+            "Colors:Window/BackgroundAlternate",
+        ])
+        .ok_or_else(|| anyhow!("missing Colors:Button/BackgroundNormal"))?,
+        accent: color(&[
+            "Colors:Selection/BackgroundNormal",
+            "Colors:View/DecorationFocus",
+        ])
+        .ok_or_else(|| anyhow!("missing Colors:Selection/BackgroundNormal"))?,
+        destructive: color(&[
+            "Colors:View/ForegroundNegative",
+            "Colors:Window/ForegroundNegative",
+        ])
+        .ok_or_else(|| anyhow!("missing Colors:View/ForegroundNegative"))?,
+        accent_text: color(&["Colors:View/ForegroundLink", "Colors:View/ForegroundActive"]),
+        ..Default::default()
+    })
+}
+
+/// KDE colors are `r,g,b` or `r,g,b,a` out of 255, or occasionally hex
+fn kde_color(value: &str) -> anyhow::Result<SRGBA> {
+    if value.starts_with('#') {
+        return rgba_from_hex(value);
+    }
+    let channels = value
+        .split(',')
+        .map(|c| c.trim().parse::<u8>())
+        .collect::<Result<Vec<_>, _>>()?;
+    match channels.as_slice() {
+        [r, g, b] => Ok(SRGBA(Srgba::<u8>::new(*r, *g, *b, u8::MAX).into_format())),
+        [r, g, b, a] => Ok(SRGBA(Srgba::<u8>::new(*r, *g, *b, *a).into_format())),
+        _ => bail!("invalid color: {}", value),
+    }
+}
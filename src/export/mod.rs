@@ -0,0 +1,186 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+pub use base16::*;
+pub use bundle::*;
+pub use editor::*;
+pub use gtk::*;
+pub use install::*;
+pub use kde::*;
+pub use preview::*;
+pub use registry::*;
+pub use swatches::*;
+pub use template::*;
+pub use terminal::*;
+pub use tokens::*;
+pub use web::*;
+
+mod base16;
+mod bundle;
+mod editor;
+mod gtk;
+mod install;
+mod kde;
+mod preview;
+mod registry;
+mod swatches;
+mod template;
+mod terminal;
+mod tokens;
+mod web;
+
+use crate::util::SRGBA;
+use cosmic_theme::{Container, Selection, Theme, Widget};
+
+/// Read a `.cosmic-theme` bundle or a theme in RON.
+/// Bundles also give the selection the theme was derived from.
+pub fn read_theme(data: &[u8]) -> anyhow::Result<(Theme<SRGBA>, Option<Selection<SRGBA>>)> {
+    match ThemeBundle::from_bytes(data) {
+        Ok(bundle) => Ok((bundle.theme, Some(bundle.selection))),
+        Err(_) => {
+            let theme = ron::from_str(std::str::from_utf8(data)?)
+                .map_err(|_| anyhow::anyhow!("not a theme bundle or theme"))?;
+            Ok((theme, None))
+        }
+    }
+}
+
+/// The selection a derived theme most likely came from
+pub fn selection_from_theme(theme: &Theme<SRGBA>) -> Selection<SRGBA> {
+    Selection {
+        background: theme.background.container,
+        primary_container: theme.primary.container,
+        secondary_container: theme.secondary.container,
+        accent: theme.accent.accent,
+        accent_text: Some(theme.accent.accent_text),
+        accent_nav_handle_text: Some(theme.accent.accent_nav_handle_text),
+        destructive: theme.destructive.destructive.default,
+    }
+}
+
+/// Set one color of a selection, named like the `Selection` field
+pub fn set_selection_color(
+    selection: &mut Selection<SRGBA>,
+    role: &str,
+    color: SRGBA,
+) -> anyhow::Result<()> {
+    match role {
+        "background" => selection.background = color,
+        "primary_container" => selection.primary_container = color,
+        "secondary_container" => selection.secondary_container = color,
+        "accent" => selection.accent = color,
+        "accent_text" => selection.accent_text = Some(color),
+        "accent_nav_handle_text" => selection.accent_nav_handle_text = Some(color),
+        "destructive" => selection.destructive = color,
+        _ => anyhow::bail!("unknown selection color: {}", role),
+    }
+    Ok(())
+}
+
+/// Every color of a derived theme, keyed by its path through the `Theme` fields
+pub fn theme_colors(theme: &Theme<SRGBA>) -> Vec<(Vec<&'static str>, SRGBA)> {
+    let mut colors = Vec::new();
+    container_colors("background", &theme.background, &mut colors);
+    container_colors("primary", &theme.primary, &mut colors);
+    container_colors("secondary", &theme.secondary, &mut colors);
+
+    colors.push((vec!["accent", "accent"], theme.accent.accent));
+    colors.push((vec!["accent", "accent_text"], theme.accent.accent_text));
+    colors.push((
+        vec!["accent", "accent_nav_handle_text"],
+        theme.accent.accent_nav_handle_text,
+    ));
+    widget_colors(
+        &["accent", "suggested"],
+        &theme.accent.suggested,
+        &mut colors,
+    );
+    widget_colors(
+        &["destructive", "destructive"],
+        &theme.destructive.destructive,
+        &mut colors,
+    );
+    colors
+}
+
+fn container_colors(
+    prefix: &'static str,
+    container: &Container<SRGBA>,
+    colors: &mut Vec<(Vec<&'static str>, SRGBA)>,
+) {
+    colors.push((vec![prefix, "container"], container.container));
+    widget_colors(
+        &[prefix, "container_component"],
+        &container.container_component,
+        colors,
+    );
+    colors.push((
+        vec![prefix, "container_divider"],
+        container.container_divider,
+    ));
+    colors.push((vec![prefix, "container_text"], container.container_text));
+    colors.push((
+        vec![prefix, "container_text_opacity_80"],
+        container.container_text_opacity_80,
+    ));
+}
+
+fn widget_colors(
+    prefix: &[&'static str],
+    widget: &Widget<SRGBA>,
+    colors: &mut Vec<(Vec<&'static str>, SRGBA)>,
+) {
+    for (name, color) in [
+        ("default", widget.default),
+        ("hover", widget.hover),
+        ("pressed", widget.pressed),
+        ("focused", widget.focused),
+        ("divider", widget.divider),
+        ("text", widget.text),
+        ("text_opacity_80", widget.text_opacity_80),
+        ("disabled", widget.disabled),
+        ("disabled_text", widget.disabled_text),
+    ] {
+        let mut path = prefix.to_vec();
+        path.push(name);
+        colors.push((path, color));
+    }
+}
+
+/// A selection with every role set, for round-trip tests
+#[cfg(test)]
+pub(crate) fn test_selection() -> Selection<SRGBA> {
+    use crate::util::rgba_from_hex;
+
+    Selection {
+        background: rgba_from_hex("#1b1b1b").unwrap(),
+        primary_container: rgba_from_hex("#2e2e2e").unwrap(),
+        secondary_container: rgba_from_hex("#474747").unwrap(),
+        accent: rgba_from_hex("#5294e2").unwrap(),
+        accent_text: Some(rgba_from_hex("#ffffff").unwrap()),
+        accent_nav_handle_text: Some(rgba_from_hex("#f2f2f2").unwrap()),
+        destructive: rgba_from_hex("#e0533f").unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::rgba_from_hex;
+
+    #[test]
+    fn selection_color_roles() {
+        let color = rgba_from_hex("#123456").unwrap();
+        let mut selection = test_selection();
+        set_selection_color(&mut selection, "accent_nav_handle_text", color).unwrap();
+        assert_eq!(selection.accent_nav_handle_text, Some(color));
+        set_selection_color(&mut selection, "secondary_container", color).unwrap();
+        assert_eq!(selection.secondary_container, color);
+
+        // role names come from D-Bus callers, and an unknown one leaves the selection alone
+        let before = selection;
+        assert!(set_selection_color(&mut selection, "foreground", color).is_err());
+        assert!(set_selection_color(&mut selection, "accent-text", color).is_err());
+        assert_eq!(selection.background, before.background);
+        assert_eq!(selection.accent_text, before.accent_text);
+    }
+}
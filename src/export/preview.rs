@@ -0,0 +1,218 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::util::{over, SRGBA};
+use cosmic_theme::Theme;
+use gettextrs::gettext;
+use gtk4::{
+    cairo::{self, FontSlant, FontWeight, Format, ImageSurface},
+    gdk_pixbuf::{Colorspace, Pixbuf},
+    glib,
+};
+use palette::{Pixel, Srgba};
+
+/// Render the nested theme surfaces with accent and destructive bars to a PNG.
+/// Only gdk-pixbuf is used, so this works without a display.
+pub fn preview_png(theme: &Theme<SRGBA>, width: i32, height: i32) -> anyhow::Result<Vec<u8>> {
+    let pixbuf = Pixbuf::new(Colorspace::Rgb, true, 8, width, height)
+        .ok_or_else(|| anyhow::anyhow!("failed to allocate a {}x{} image", width, height))?;
+
+    let bg = theme.background.container;
+    let primary = over(&theme.primary.container, &bg);
+    let secondary = over(&theme.secondary.container, &primary);
+    let fill = |color: &SRGBA, x: i32, y: i32, w: i32, h: i32| {
+        let [r, g, b, a]: [u8; 4] = Srgba::into_raw(color.into_format());
+        pixbuf
+            .new_subpixbuf(x, y, w.max(1), h.max(1))
+            .fill(u32::from_be_bytes([r, g, b, a]));
+    };
+
+    let margin = (width.min(height) / 10).max(1);
+    fill(&bg, 0, 0, width, height);
+    fill(
+        &primary,
+        margin,
+        margin,
+        width - 2 * margin,
+        height - 2 * margin,
+    );
+    fill(
+        &secondary,
+        2 * margin,
+        2 * margin,
+        width / 2 - 2 * margin,
+        height - 4 * margin,
+    );
+
+    let bar_height = (height - 5 * margin) / 2;
+    fill(
+        &over(&theme.accent.accent, &primary),
+        width / 2 + margin,
+        2 * margin,
+        width / 2 - 3 * margin,
+        bar_height,
+    );
+    fill(
+        &over(&theme.destructive.destructive.default, &primary),
+        width / 2 + margin,
+        3 * margin + bar_height,
+        width / 2 - 3 * margin,
+        bar_height,
+    );
+
+    Ok(pixbuf.save_to_bufferv("png", &[])?)
+}
+
+// Sizes of the preview hierarchy in `ThemeEditor::new`, in logical pixels
+const SCREENSHOT_WIDTH: f64 = 480.0;
+const SPACING: f64 = 8.0;
+const RADIUS: f64 = 8.0;
+const FONT_SIZE: f64 = 13.0;
+const LABEL_HEIGHT: f64 = FONT_SIZE + 4.0 * SPACING;
+const COMPONENT_HEIGHT: f64 = 50.0;
+
+/// Render the preview hierarchy of the editor to a PNG at `scale`:
+/// background, primary and secondary containers, each with a component and a divider.
+/// Drawing is done by cairo on an image surface, so no display is needed.
+pub fn preview_screenshot_png(theme: &Theme<SRGBA>, scale: f64) -> anyhow::Result<Vec<u8>> {
+    let containers = [
+        (
+            &theme.background,
+            gettext("Background"),
+            gettext("Background Component"),
+        ),
+        (
+            &theme.primary,
+            gettext("Primary Container"),
+            gettext("Primary Container Component"),
+        ),
+        (
+            &theme.secondary,
+            gettext("Secondary Container"),
+            gettext("Secondary Container Component"),
+        ),
+    ];
+
+    let height = container_height(containers.len());
+    let width_px = (SCREENSHOT_WIDTH * scale).ceil() as i32;
+    let height_px = (height * scale).ceil() as i32;
+    let mut surface = ImageSurface::create(Format::ARgb32, width_px, height_px)?;
+    {
+        let cr = cairo::Context::new(&surface)?;
+        cr.scale(scale, scale);
+        cr.select_font_face("Sans", FontSlant::Normal, FontWeight::Normal);
+        cr.set_font_size(FONT_SIZE);
+
+        // the background container covers the whole image
+        set_source(&cr, &theme.background.container);
+        cr.paint()?;
+        let (mut x, mut y, mut w) = (0.0, 0.0, SCREENSHOT_WIDTH);
+        for (i, (container, name, component_name)) in containers.iter().enumerate() {
+            let h = container_height(containers.len() - i);
+            if i > 0 {
+                rounded_rectangle(&cr, x, y, w, h);
+                set_source(&cr, &container.container);
+                cr.fill()?;
+            }
+
+            label(&cr, &container.container_text, x, y, w, LABEL_HEIGHT, name)?;
+            let mut cy = y + LABEL_HEIGHT + SPACING;
+            rounded_rectangle(&cr, x + SPACING, cy, w - 2.0 * SPACING, COMPONENT_HEIGHT);
+            set_source(&cr, &container.container_component.default);
+            cr.fill()?;
+            label(
+                &cr,
+                &container.container_component.text,
+                x + SPACING,
+                cy,
+                w - 2.0 * SPACING,
+                COMPONENT_HEIGHT,
+                component_name,
+            )?;
+
+            cy += COMPONENT_HEIGHT + 2.0 * SPACING;
+            cr.rectangle(x + SPACING, cy, w - 2.0 * SPACING, 1.0);
+            set_source(&cr, &container.container_divider);
+            cr.fill()?;
+
+            x += 2.0 * SPACING;
+            y = cy + 1.0 + 2.0 * SPACING;
+            w -= 4.0 * SPACING;
+        }
+    }
+
+    // cairo stores premultiplied native-endian ARGB, pixbuf wants straight RGBA
+    let stride = surface.stride() as usize;
+    let data = surface.data()?;
+    let mut rgba = Vec::with_capacity((width_px * height_px * 4) as usize);
+    for row in data.chunks(stride).take(height_px as usize) {
+        for px in row[..width_px as usize * 4].chunks_exact(4) {
+            let [b, g, r, a] = u32::from_ne_bytes([px[0], px[1], px[2], px[3]]).to_le_bytes();
+            let unpremultiply = |c: u8| match a {
+                0 => 0,
+                a => ((c as u32 * 255 + a as u32 / 2) / a as u32) as u8,
+            };
+            rgba.extend([unpremultiply(r), unpremultiply(g), unpremultiply(b), a]);
+        }
+    }
+    let pixbuf = Pixbuf::from_bytes(
+        &glib::Bytes::from_owned(rgba),
+        Colorspace::Rgb,
+        true,
+        8,
+        width_px,
+        height_px,
+        width_px * 4,
+    );
+
+    Ok(pixbuf.save_to_bufferv("png", &[])?)
+}
+
+/// Height of a container holding `depth - 1` nested containers
+fn container_height(depth: usize) -> f64 {
+    let own = LABEL_HEIGHT + COMPONENT_HEIGHT + 1.0 + 6.0 * SPACING;
+    match depth {
+        0 => 0.0,
+        1 => own,
+        depth => own + container_height(depth - 1),
+    }
+}
+
+fn set_source(cr: &cairo::Context, color: &SRGBA) {
+    cr.set_source_rgba(
+        color.red.into(),
+        color.green.into(),
+        color.blue.into(),
+        color.alpha.into(),
+    );
+}
+
+fn rounded_rectangle(cr: &cairo::Context, x: f64, y: f64, w: f64, h: f64) {
+    let r = RADIUS.min(w / 2.0).min(h / 2.0);
+    let quarter = std::f64::consts::FRAC_PI_2;
+    cr.new_sub_path();
+    cr.arc(x + w - r, y + r, r, -quarter, 0.0);
+    cr.arc(x + w - r, y + h - r, r, 0.0, quarter);
+    cr.arc(x + r, y + h - r, r, quarter, 2.0 * quarter);
+    cr.arc(x + r, y + r, r, 2.0 * quarter, 3.0 * quarter);
+    cr.close_path();
+}
+
+/// Draw `text` centered in the given box
+fn label(
+    cr: &cairo::Context,
+    color: &SRGBA,
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+    text: &str,
+) -> anyhow::Result<()> {
+    let extents = cr.text_extents(text)?;
+    set_source(cr, color);
+    cr.move_to(
+        x + (w - extents.width) / 2.0 - extents.x_bearing,
+        y + (h - extents.height) / 2.0 - extents.y_bearing,
+    );
+    cr.show_text(text)?;
+    Ok(())
+}
@@ -0,0 +1,453 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use super::*;
+use crate::{syntax::SyntaxPalette, util::SRGBA};
+use cosmic_theme::{Gtk4Output, Selection, Theme};
+use ron::ser::PrettyConfig;
+
+/// A file format a theme can be exported to
+pub trait ThemeExporter {
+    /// Stable identifier, used by the command line
+    fn id(&self) -> &'static str;
+
+    fn name(&self) -> &'static str;
+
+    fn extension(&self) -> &str;
+
+    fn mime_type(&self) -> &str;
+
+    fn export(&self, theme: &Theme<SRGBA>, selection: &Selection<SRGBA>)
+        -> anyhow::Result<Vec<u8>>;
+
+    /// Suggested file name for the exported theme
+    fn file_name(&self, theme_name: &str) -> String {
+        format!("{}.{}", theme_name, self.extension())
+    }
+}
+
+/// A file format a selection can be imported from
+pub trait ThemeImporter {
+    fn name(&self) -> &str;
+
+    fn extension(&self) -> &str;
+
+    fn mime_type(&self) -> &str;
+
+    fn import(&self, data: &[u8]) -> anyhow::Result<Selection<SRGBA>>;
+}
+
+type Render = Box<dyn Fn(&Theme<SRGBA>, &Selection<SRGBA>) -> anyhow::Result<String>>;
+
+/// Exporter for text formats
+pub struct TextExporter {
+    id: &'static str,
+    name: &'static str,
+    extension: &'static str,
+    mime_type: &'static str,
+    render: Render,
+}
+
+impl TextExporter {
+    pub fn new(
+        id: &'static str,
+        name: &'static str,
+        extension: &'static str,
+        mime_type: &'static str,
+        render: impl Fn(&Theme<SRGBA>, &Selection<SRGBA>) -> anyhow::Result<String> + 'static,
+    ) -> Self {
+        Self {
+            id,
+            name,
+            extension,
+            mime_type,
+            render: Box::new(render),
+        }
+    }
+}
+
+impl ThemeExporter for TextExporter {
+    fn id(&self) -> &'static str {
+        self.id
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn extension(&self) -> &str {
+        self.extension
+    }
+
+    fn mime_type(&self) -> &str {
+        self.mime_type
+    }
+
+    fn export(
+        &self,
+        theme: &Theme<SRGBA>,
+        selection: &Selection<SRGBA>,
+    ) -> anyhow::Result<Vec<u8>> {
+        (self.render)(theme, selection).map(String::into_bytes)
+    }
+}
+
+type Encode = Box<dyn Fn(&Theme<SRGBA>, &Selection<SRGBA>) -> anyhow::Result<Vec<u8>>>;
+
+/// Exporter for binary formats
+pub struct BinaryExporter {
+    id: &'static str,
+    name: &'static str,
+    extension: &'static str,
+    mime_type: &'static str,
+    encode: Encode,
+}
+
+impl BinaryExporter {
+    pub fn new(
+        id: &'static str,
+        name: &'static str,
+        extension: &'static str,
+        mime_type: &'static str,
+        encode: impl Fn(&Theme<SRGBA>, &Selection<SRGBA>) -> anyhow::Result<Vec<u8>> + 'static,
+    ) -> Self {
+        Self {
+            id,
+            name,
+            extension,
+            mime_type,
+            encode: Box::new(encode),
+        }
+    }
+}
+
+impl ThemeExporter for BinaryExporter {
+    fn id(&self) -> &'static str {
+        self.id
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn extension(&self) -> &str {
+        self.extension
+    }
+
+    fn mime_type(&self) -> &str {
+        self.mime_type
+    }
+
+    fn export(
+        &self,
+        theme: &Theme<SRGBA>,
+        selection: &Selection<SRGBA>,
+    ) -> anyhow::Result<Vec<u8>> {
+        (self.encode)(theme, selection)
+    }
+}
+
+impl ThemeExporter for Template {
+    fn id(&self) -> &'static str {
+        self.id
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn extension(&self) -> &str {
+        self.file_name
+            .rsplit_once('.')
+            .map(|(_, extension)| extension)
+            .unwrap_or_default()
+    }
+
+    fn mime_type(&self) -> &str {
+        self.mime_type
+    }
+
+    fn export(&self, theme: &Theme<SRGBA>, _: &Selection<SRGBA>) -> anyhow::Result<Vec<u8>> {
+        TemplateContext::new(theme)
+            .render(self.source)
+            .map(String::into_bytes)
+    }
+
+    fn file_name(&self, _: &str) -> String {
+        self.file_name.to_string()
+    }
+}
+
+/// Importer for text formats
+pub struct TextImporter {
+    name: &'static str,
+    extension: &'static str,
+    mime_type: &'static str,
+    parse: fn(&str) -> anyhow::Result<Selection<SRGBA>>,
+}
+
+impl TextImporter {
+    pub fn new(
+        name: &'static str,
+        extension: &'static str,
+        mime_type: &'static str,
+        parse: fn(&str) -> anyhow::Result<Selection<SRGBA>>,
+    ) -> Self {
+        Self {
+            name,
+            extension,
+            mime_type,
+            parse,
+        }
+    }
+}
+
+impl ThemeImporter for TextImporter {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn extension(&self) -> &str {
+        self.extension
+    }
+
+    fn mime_type(&self) -> &str {
+        self.mime_type
+    }
+
+    fn import(&self, data: &[u8]) -> anyhow::Result<Selection<SRGBA>> {
+        (self.parse)(std::str::from_utf8(data)?)
+    }
+}
+
+/// Importer for binary formats
+pub struct BinaryImporter {
+    name: &'static str,
+    extension: &'static str,
+    mime_type: &'static str,
+    parse: fn(&[u8]) -> anyhow::Result<Selection<SRGBA>>,
+}
+
+impl BinaryImporter {
+    pub fn new(
+        name: &'static str,
+        extension: &'static str,
+        mime_type: &'static str,
+        parse: fn(&[u8]) -> anyhow::Result<Selection<SRGBA>>,
+    ) -> Self {
+        Self {
+            name,
+            extension,
+            mime_type,
+            parse,
+        }
+    }
+}
+
+impl ThemeImporter for BinaryImporter {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn extension(&self) -> &str {
+        self.extension
+    }
+
+    fn mime_type(&self) -> &str {
+        self.mime_type
+    }
+
+    fn import(&self, data: &[u8]) -> anyhow::Result<Selection<SRGBA>> {
+        (self.parse)(data)
+    }
+}
+
+/// Every supported export format.
+/// The editor exporters use `syntax` when given, and otherwise derive a syntax palette.
+pub fn exporters(syntax: Option<SyntaxPalette>) -> Vec<Box<dyn ThemeExporter>> {
+    let syntax = move |theme: &Theme<SRGBA>| syntax.unwrap_or_else(|| SyntaxPalette::derive(theme));
+
+    let mut exporters: Vec<Box<dyn ThemeExporter>> = vec![
+        Box::new(TextExporter::new(
+            "css",
+            "COSMIC GTK 4",
+            "css",
+            "text/css",
+            |theme, _| Ok(theme.as_css()),
+        )),
+        Box::new(TextExporter::new(
+            "ron",
+            "COSMIC RON",
+            "ron",
+            "text/plain",
+            |theme, _| Ok(ron::ser::to_string_pretty(theme, PrettyConfig::new())?),
+        )),
+        Box::new(TextExporter::new(
+            "gtk3",
+            "GTK 3",
+            "gtk.css",
+            "text/css",
+            |theme, _| Ok(theme.as_gtk3_css()),
+        )),
+        Box::new(TextExporter::new(
+            "adwaita",
+            "libadwaita",
+            "gtk.css",
+            "text/css",
+            |theme, _| Ok(theme.as_adwaita_css()),
+        )),
+        Box::new(TextExporter::new(
+            "tokens",
+            "Design Tokens",
+            "tokens.json",
+            "application/json",
+            |theme, selection| Ok(tokens_from_theme(theme, selection)),
+        )),
+        Box::new(TextExporter::new(
+            "css-vars",
+            "CSS Custom Properties",
+            "vars.css",
+            "text/css",
+            |theme, _| Ok(css_custom_properties(theme)),
+        )),
+        Box::new(TextExporter::new(
+            "scss",
+            "SCSS Variables",
+            "scss",
+            "text/x-scss",
+            |theme, _| Ok(scss_variables(theme)),
+        )),
+        Box::new(TextExporter::new(
+            "less",
+            "LESS Variables",
+            "less",
+            "text/x-less",
+            |theme, _| Ok(less_variables(theme)),
+        )),
+        Box::new(TextExporter::new(
+            "neovim",
+            "Neovim",
+            "lua",
+            "text/x-lua",
+            move |theme, _| Ok(neovim_colorscheme(theme, &syntax(theme))),
+        )),
+        Box::new(TextExporter::new(
+            "vscode",
+            "VS Code",
+            "color-theme.json",
+            "application/json",
+            move |theme, _| Ok(vscode_theme(theme, &syntax(theme))),
+        )),
+        Box::new(TextExporter::new(
+            "base16",
+            "Base16",
+            "yaml",
+            "application/x-yaml",
+            move |theme, _| Ok(base16_from_theme(theme, &syntax(theme))),
+        )),
+        Box::new(TextExporter::new(
+            "gpl",
+            "GIMP Palette",
+            "gpl",
+            "application/x-gimp-palette",
+            |theme, selection| Ok(gpl_from_theme(theme, selection)),
+        )),
+        Box::new(BinaryExporter::new(
+            "ase",
+            "Adobe Swatch Exchange",
+            "ase",
+            "application/x-adobe-ase",
+            |theme, selection| Ok(ase_from_theme(theme, selection)),
+        )),
+        Box::new(TextExporter::new(
+            "soc",
+            "LibreOffice Palette",
+            "soc",
+            "application/xml",
+            |theme, selection| Ok(soc_from_theme(theme, selection)),
+        )),
+    ];
+    exporters.extend(
+        BUILTIN_TEMPLATES
+            .iter()
+            .map(|template| Box::new(*template) as Box<dyn ThemeExporter>),
+    );
+    exporters
+}
+
+/// Every supported import format
+pub fn importers() -> Vec<Box<dyn ThemeImporter>> {
+    vec![
+        Box::new(TextImporter::new(
+            "Design Tokens",
+            "tokens.json",
+            "application/json",
+            selection_from_tokens,
+        )),
+        Box::new(TextImporter::new(
+            "Base16/Base24",
+            "yaml",
+            "application/x-yaml",
+            selection_from_base16,
+        )),
+        Box::new(BinaryImporter::new(
+            "iTerm2",
+            "itermcolors",
+            "application/x-plist",
+            selection_from_iterm,
+        )),
+        Box::new(TextImporter::new(
+            "Alacritty (TOML)",
+            "toml",
+            "application/toml",
+            selection_from_alacritty_toml,
+        )),
+        Box::new(TextImporter::new(
+            "Alacritty (YAML)",
+            "yml",
+            "application/x-yaml",
+            selection_from_alacritty_yaml,
+        )),
+        Box::new(TextImporter::new(
+            "Kitty",
+            "conf",
+            "text/plain",
+            selection_from_kitty,
+        )),
+        Box::new(TextImporter::new(
+            "VS Code",
+            "json",
+            "application/json",
+            selection_from_vscode,
+        )),
+        Box::new(TextImporter::new(
+            "GTK CSS",
+            "css",
+            "text/css",
+            selection_from_gtk_css,
+        )),
+        Box::new(TextImporter::new(
+            "KDE Color Scheme",
+            "colors",
+            "text/plain",
+            selection_from_kde_colors,
+        )),
+        Box::new(TextImporter::new(
+            "GIMP Palette",
+            "gpl",
+            "application/x-gimp-palette",
+            selection_from_gpl,
+        )),
+        Box::new(BinaryImporter::new(
+            "Adobe Swatch Exchange",
+            "ase",
+            "application/x-adobe-ase",
+            selection_from_ase,
+        )),
+        Box::new(TextImporter::new(
+            "LibreOffice Palette",
+            "soc",
+            "application/xml",
+            selection_from_soc,
+        )),
+    ]
+}
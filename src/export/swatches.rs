@@ -0,0 +1,369 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Palette interchange formats for graphics applications.
+//! Exports hold the selection followed by every derived theme color,
+//! imports go through the same role assignment as colors extracted from an image.
+
+use super::theme_colors;
+use crate::util::{rgba_from_hex, SRGBA};
+use anyhow::{anyhow, bail, Context};
+use cosmic_theme::{Selection, Theme};
+use gtk4::glib;
+use palette::{IntoColor, Lab, Pixel, Srgb, Srgba};
+use std::fmt::Write;
+
+/// A named palette entry
+pub type Swatch = (String, SRGBA);
+
+/// The selection colors, in the order they are shown in the editor
+pub fn selection_swatches(selection: &Selection<SRGBA>) -> Vec<Swatch> {
+    let mut swatches = vec![
+        ("background".to_string(), selection.background),
+        ("primary_container".to_string(), selection.primary_container),
+        (
+            "secondary_container".to_string(),
+            selection.secondary_container,
+        ),
+        ("accent".to_string(), selection.accent),
+        ("destructive".to_string(), selection.destructive),
+    ];
+    swatches.extend(
+        selection
+            .accent_text
+            .map(|c| ("accent_text".to_string(), c)),
+    );
+    swatches.extend(
+        selection
+            .accent_nav_handle_text
+            .map(|c| ("accent_nav_handle_text".to_string(), c)),
+    );
+    swatches
+}
+
+/// Every derived theme color, named by its path through the `Theme` fields
+pub fn theme_swatches(theme: &Theme<SRGBA>) -> Vec<Swatch> {
+    theme_colors(theme)
+        .into_iter()
+        .map(|(path, c)| (path.join("."), c))
+        .collect()
+}
+
+/// Assign roles to imported swatches the same way as to colors extracted from an image
+pub fn selection_from_swatches(swatches: Vec<SRGBA>) -> anyhow::Result<Selection<SRGBA>> {
+    let mut unique: Vec<SRGBA> = Vec::with_capacity(swatches.len());
+    for swatch in swatches {
+        if !unique.contains(&swatch) {
+            unique.push(swatch);
+        }
+    }
+    unique
+        .try_into()
+        .map_err(|_| anyhow!("the palette does not have enough colors for a theme"))
+}
+
+fn rgb_bytes(c: &SRGBA) -> [u8; 3] {
+    let [r, g, b, _]: [u8; 4] = Srgba::into_raw(c.into_format());
+    [r, g, b]
+}
+
+/// GIMP and Inkscape `.gpl` palette. Alpha is dropped, as the format has none.
+pub fn gpl_from_theme(theme: &Theme<SRGBA>, selection: &Selection<SRGBA>) -> String {
+    let mut gpl = format!("GIMP Palette\nName: {}\nColumns: 8\n#\n", theme.name);
+    for (heading, swatches) in [
+        ("Selection", selection_swatches(selection)),
+        ("Theme", theme_swatches(theme)),
+    ] {
+        let _ = writeln!(gpl, "# {}", heading);
+        for (name, c) in swatches {
+            let [r, g, b] = rgb_bytes(&c);
+            let _ = writeln!(gpl, "{:3} {:3} {:3}\t{}", r, g, b, name);
+        }
+    }
+    gpl
+}
+
+pub fn selection_from_gpl(gpl: &str) -> anyhow::Result<Selection<SRGBA>> {
+    let mut lines = gpl.lines().map(str::trim);
+    if lines.next() != Some("GIMP Palette") {
+        bail!("not a GIMP palette");
+    }
+    let swatches = lines
+        .filter(|line| {
+            !line.is_empty()
+                && !line.starts_with('#')
+                && !line.starts_with("Name:")
+                && !line.starts_with("Columns:")
+        })
+        .map(|line| {
+            let channels = line
+                .split_whitespace()
+                .take(3)
+                .map(str::parse::<u8>)
+                .collect::<Result<Vec<_>, _>>()
+                .with_context(|| format!("invalid palette entry: {}", line))?;
+            match channels.as_slice() {
+                [r, g, b] => Ok(SRGBA(Srgba::<u8>::new(*r, *g, *b, u8::MAX).into_format())),
+                _ => Err(anyhow!("invalid palette entry: {}", line)),
+            }
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    selection_from_swatches(swatches)
+}
+
+const ASE_GROUP_START: u16 = 0xc001;
+const ASE_GROUP_END: u16 = 0xc002;
+const ASE_COLOR: u16 = 0x0001;
+const ASE_NORMAL_COLOR: u16 = 2;
+
+/// Adobe Swatch Exchange, with the selection and the theme in separate groups
+pub fn ase_from_theme(theme: &Theme<SRGBA>, selection: &Selection<SRGBA>) -> Vec<u8> {
+    ase_from_swatches(&[
+        ("Selection", selection_swatches(selection)),
+        ("Theme", theme_swatches(theme)),
+    ])
+}
+
+fn ase_from_swatches(groups: &[(&str, Vec<Swatch>)]) -> Vec<u8> {
+    fn ase_name(name: &str) -> Vec<u8> {
+        let utf16: Vec<u16> = name.encode_utf16().chain([0]).collect();
+        let mut block = (utf16.len() as u16).to_be_bytes().to_vec();
+        block.extend(utf16.iter().flat_map(|c| c.to_be_bytes()));
+        block
+    }
+    fn ase_block(blocks: &mut Vec<u8>, kind: u16, block: &[u8]) {
+        blocks.extend(kind.to_be_bytes());
+        blocks.extend((block.len() as u32).to_be_bytes());
+        blocks.extend(block);
+    }
+
+    let mut blocks = Vec::new();
+    let mut count: u32 = 0;
+    for (group, swatches) in groups {
+        ase_block(&mut blocks, ASE_GROUP_START, &ase_name(group));
+        for (name, c) in swatches {
+            let mut block = ase_name(name);
+            block.extend(b"RGB ");
+            for channel in [c.red, c.green, c.blue] {
+                block.extend(channel.to_be_bytes());
+            }
+            block.extend(ASE_NORMAL_COLOR.to_be_bytes());
+            ase_block(&mut blocks, ASE_COLOR, &block);
+        }
+        ase_block(&mut blocks, ASE_GROUP_END, &[]);
+        count += swatches.len() as u32 + 2;
+    }
+
+    let mut ase = b"ASEF".to_vec();
+    ase.extend(1u16.to_be_bytes());
+    ase.extend(0u16.to_be_bytes());
+    ase.extend(count.to_be_bytes());
+    ase.extend(blocks);
+    ase
+}
+
+pub fn selection_from_ase(ase: &[u8]) -> anyhow::Result<Selection<SRGBA>> {
+    selection_from_swatches(swatches_from_ase(ase)?)
+}
+
+/// Every color of an ASE file, in file order. Groups are flattened.
+fn swatches_from_ase(ase: &[u8]) -> anyhow::Result<Vec<SRGBA>> {
+    let mut reader = AseReader(ase);
+    if reader.take(4)? != b"ASEF" {
+        bail!("not an Adobe Swatch Exchange file");
+    }
+    reader.take(4)?;
+    let count = reader.u32()?;
+
+    let mut swatches = Vec::new();
+    for _ in 0..count {
+        let kind = reader.u16()?;
+        let len = reader.u32()? as usize;
+        let mut block = AseReader(reader.take(len)?);
+        if kind != ASE_COLOR {
+            continue;
+        }
+        let name_len = block.u16()? as usize;
+        block.take(name_len * 2)?;
+        let model = block.take(4)?;
+        let color = match model {
+            b"RGB " => Srgb::new(block.f32()?, block.f32()?, block.f32()?),
+            b"Gray" => {
+                let gray = block.f32()?;
+                Srgb::new(gray, gray, gray)
+            }
+            b"CMYK" => {
+                let (c, m, y, k) = (block.f32()?, block.f32()?, block.f32()?, block.f32()?);
+                Srgb::new(
+                    (1.0 - c) * (1.0 - k),
+                    (1.0 - m) * (1.0 - k),
+                    (1.0 - y) * (1.0 - k),
+                )
+            }
+            b"LAB " => {
+                let lab = Lab::new(block.f32()? * 100.0, block.f32()?, block.f32()?);
+                lab.into_color()
+            }
+            _ => bail!(
+                "unsupported color model: {}",
+                String::from_utf8_lossy(model)
+            ),
+        };
+        swatches.push(SRGBA(Srgba::from(color)));
+    }
+    Ok(swatches)
+}
+
+struct AseReader<'a>(&'a [u8]);
+
+impl<'a> AseReader<'a> {
+    fn take(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        if self.0.len() < len {
+            bail!("unexpected end of file");
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn u16(&mut self) -> anyhow::Result<u16> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into()?))
+    }
+
+    fn u32(&mut self) -> anyhow::Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn f32(&mut self) -> anyhow::Result<f32> {
+        Ok(f32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+}
+
+/// LibreOffice `.soc` color table. Alpha is dropped, as the format has none.
+pub fn soc_from_theme(theme: &Theme<SRGBA>, selection: &Selection<SRGBA>) -> String {
+    let mut soc = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<ooo:color-table",
+        " xmlns:office=\"urn:oasis:names:tc:opendocument:xmlns:office:1.0\"",
+        " xmlns:draw=\"urn:oasis:names:tc:opendocument:xmlns:drawing:1.0\"",
+        " xmlns:xlink=\"http://www.w3.org/1999/xlink\"",
+        " xmlns:svg=\"http://www.w3.org/2000/svg\"",
+        " xmlns:ooo=\"http://openoffice.org/2004/office\">\n",
+    ));
+    let swatches = selection_swatches(selection)
+        .into_iter()
+        .map(|(name, c)| (format!("selection.{}", name), c))
+        .chain(theme_swatches(theme));
+    for (name, c) in swatches {
+        let [r, g, b] = rgb_bytes(&c);
+        let _ = writeln!(
+            soc,
+            "  <draw:color draw:name=\"{}\" draw:color=\"#{:02x}{:02x}{:02x}\"/>",
+            glib::markup_escape_text(&name),
+            r,
+            g,
+            b
+        );
+    }
+    soc.push_str("</ooo:color-table>\n");
+    soc
+}
+
+pub fn selection_from_soc(soc: &str) -> anyhow::Result<Selection<SRGBA>> {
+    if !soc.contains("color-table") {
+        bail!("not a LibreOffice color table");
+    }
+    let swatches = soc
+        .split("draw:color=\"")
+        .skip(1)
+        .filter_map(|attr| attr.split_once('"'))
+        .map(|(value, _)| rgba_from_hex(value))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    selection_from_swatches(swatches)
+}
+
+/// Colors from pasted text, such as `#1b1b1b, #2e3440` or a coolors.co
+/// palette like `264653-2a9d8f-e9c46a`. Colors without a `#` need all six
+/// or eight digits, so short words in the text are not taken for colors.
+pub fn swatches_from_text(text: &str) -> Vec<SRGBA> {
+    text.split(|c: char| c.is_whitespace() || ",;-/".contains(c))
+        .map(|token| token.trim_matches(|c: char| "\"'()[]{}".contains(c)))
+        .filter(|token| {
+            let digits = token.trim_start_matches('#');
+            let lengths: &[usize] = if token.starts_with('#') {
+                &[3, 4, 6, 8]
+            } else {
+                &[6, 8]
+            };
+            lengths.contains(&digits.len()) && digits.chars().all(|c| c.is_ascii_hexdigit())
+        })
+        .filter_map(|token| rgba_from_hex(token).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn swatch(name: &str, hex: &str) -> Swatch {
+        (name.to_string(), rgba_from_hex(hex).unwrap())
+    }
+
+    #[test]
+    fn ase_round_trip() {
+        let groups = [
+            (
+                "Selection",
+                vec![swatch("background", "#1b1b1b"), swatch("accent", "#5294e2")],
+            ),
+            ("Thème", vec![swatch("destructive · default", "#e0533f")]),
+        ];
+        let colors: Vec<SRGBA> = groups
+            .iter()
+            .flat_map(|(_, swatches)| swatches.iter().map(|(_, c)| *c))
+            .collect();
+        assert_eq!(
+            swatches_from_ase(&ase_from_swatches(&groups)).unwrap(),
+            colors
+        );
+    }
+
+    #[test]
+    fn ase_color_models() {
+        let mut ase = b"ASEF\0\x01\0\0\0\0\0\x02".to_vec();
+        for (model, channels) in [(b"Gray", vec![0.5f32]), (b"CMYK", vec![0.0, 1.0, 1.0, 0.0])] {
+            let mut block = vec![0, 1, 0, 0];
+            block.extend(model);
+            block.extend(channels.iter().flat_map(|c| c.to_be_bytes()));
+            block.extend(ASE_NORMAL_COLOR.to_be_bytes());
+            ase.extend(ASE_COLOR.to_be_bytes());
+            ase.extend((block.len() as u32).to_be_bytes());
+            ase.extend(block);
+        }
+        assert_eq!(
+            swatches_from_ase(&ase).unwrap(),
+            [
+                SRGBA(Srgba::new(0.5, 0.5, 0.5, 1.0)),
+                SRGBA(Srgba::new(1.0, 0.0, 0.0, 1.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_ase() {
+        let ase = ase_from_swatches(&[("Selection", vec![swatch("accent", "#5294e2")])]);
+        assert!(swatches_from_ase(&ase[..ase.len() - 3]).is_err());
+        assert!(swatches_from_ase(b"RIFF\0\x01\0\0\0\0\0\0").is_err());
+    }
+
+    #[test]
+    fn colors_in_text() {
+        assert_eq!(
+            swatches_from_text("264653-2a9d8f, #fa0 and beef cafe"),
+            [
+                rgba_from_hex("#264653").unwrap(),
+                rgba_from_hex("#2a9d8f").unwrap(),
+                rgba_from_hex("#ffaa00").unwrap(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,331 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use super::theme_colors;
+use crate::{
+    metadata::ThemeVariant,
+    util::{css_rgba, hex_from_rgba, SRGBA},
+};
+use anyhow::{anyhow, bail};
+use cosmic_theme::Theme;
+use gtk4::glib;
+use palette::{rgb::Srgba, Clamp, FromColor, Hsl, Pixel, Srgb};
+use std::{collections::HashMap, fs, path::PathBuf};
+
+/// A config file rendered from the theme.
+/// Add a file to `templates/` and an entry to [`BUILTIN_TEMPLATES`] to support another app.
+#[derive(Clone, Copy)]
+pub struct Template {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub file_name: &'static str,
+    pub mime_type: &'static str,
+    pub source: &'static str,
+}
+
+pub const BUILTIN_TEMPLATES: &[Template] = &[
+    Template {
+        id: "sway",
+        name: "Sway/i3",
+        file_name: "sway-colors.conf",
+        mime_type: "text/plain",
+        source: include_str!("templates/sway.conf"),
+    },
+    Template {
+        id: "waybar",
+        name: "Waybar",
+        file_name: "waybar-colors.css",
+        mime_type: "text/css",
+        source: include_str!("templates/waybar.css"),
+    },
+    Template {
+        id: "mako",
+        name: "Mako",
+        file_name: "mako.conf",
+        mime_type: "text/plain",
+        source: include_str!("templates/mako.conf"),
+    },
+    Template {
+        id: "fuzzel",
+        name: "Fuzzel",
+        file_name: "fuzzel-colors.ini",
+        mime_type: "text/plain",
+        source: include_str!("templates/fuzzel.ini"),
+    },
+    Template {
+        id: "rofi",
+        name: "Rofi",
+        file_name: "cosmic.rasi",
+        mime_type: "text/plain",
+        source: include_str!("templates/rofi.rasi"),
+    },
+];
+
+/// Values a template can refer to
+pub struct TemplateContext {
+    pub name: String,
+    pub colors: HashMap<String, SRGBA>,
+}
+
+impl TemplateContext {
+    /// Every color is available by its full path, e.g. `background_container_component_hover`,
+    /// and the common surfaces by a short name, e.g. `background`, `background_text`.
+    pub fn new(theme: &Theme<SRGBA>) -> Self {
+        let mut colors: HashMap<String, SRGBA> = theme_colors(theme)
+            .into_iter()
+            .map(|(path, color)| (path.join("_"), color))
+            .collect();
+
+        for (prefix, container) in [
+            ("background", &theme.background),
+            ("primary", &theme.primary),
+            ("secondary", &theme.secondary),
+        ] {
+            let component = &container.container_component;
+            colors.insert(prefix.into(), container.container);
+            colors.insert(format!("{}_text", prefix), container.container_text);
+            colors.insert(format!("{}_divider", prefix), container.container_divider);
+            colors.insert(format!("{}_component", prefix), component.default);
+            colors.insert(format!("{}_component_text", prefix), component.text);
+        }
+        colors.insert("accent".into(), theme.accent.accent);
+        colors.insert("accent_text".into(), theme.accent.accent_text);
+        colors.insert("suggested".into(), theme.accent.suggested.default);
+        colors.insert("suggested_text".into(), theme.accent.suggested.text);
+        colors.insert("destructive".into(), theme.destructive.destructive.default);
+        colors.insert(
+            "destructive_text".into(),
+            theme.destructive.destructive.text,
+        );
+
+        Self {
+            name: theme.name.clone(),
+            colors,
+        }
+    }
+
+    /// Replace every `{{ color.format }}` in `source`. The format defaults to `hex`.
+    /// A literal `{{` is written as `\{{`.
+    pub fn render(&self, source: &str) -> anyhow::Result<String> {
+        let mut output = String::with_capacity(source.len());
+        let mut rest = source;
+
+        while let Some(start) = rest.find("{{") {
+            if rest[..start].ends_with('\\') {
+                output.push_str(&rest[..start - 1]);
+                output.push_str("{{");
+                rest = &rest[start + 2..];
+                continue;
+            }
+            output.push_str(&rest[..start]);
+            let end = rest[start..]
+                .find("}}")
+                .ok_or_else(|| anyhow!("unclosed {{{{ in template"))?
+                + start;
+            output.push_str(&self.eval(rest[start + 2..end].trim())?);
+            rest = &rest[end + 2..];
+        }
+        output.push_str(rest);
+        Ok(output)
+    }
+
+    /// `color[.format] [| filter(args)]...`, where a filter may also be a format name
+    fn eval(&self, expr: &str) -> anyhow::Result<String> {
+        if expr == "name" {
+            return Ok(self.name.clone());
+        }
+
+        let mut segments = expr.split('|').map(str::trim);
+        let variable = segments.next().unwrap_or_default();
+        let (name, mut format) = variable.split_once('.').unwrap_or((variable, "hex"));
+        let mut color = *self
+            .colors
+            .get(name)
+            .ok_or_else(|| anyhow!("unknown theme color {}", name))?;
+
+        for filter in segments {
+            let (filter, args) = match filter.split_once('(') {
+                Some((filter, args)) => (
+                    filter.trim(),
+                    args.strip_suffix(')')
+                        .ok_or_else(|| anyhow!("unclosed ( in filter {}", filter))?,
+                ),
+                None => (filter, ""),
+            };
+            if args.is_empty() && FORMATS.contains(&filter) {
+                format = filter;
+                continue;
+            }
+            let amount: f32 = args
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("filter {} expects a number", filter))?;
+            color = apply_filter(color, filter, amount)?;
+        }
+        format_color(&color, format)
+    }
+}
+
+const FORMATS: &[&str] = &[
+    "hex",
+    "hexa",
+    "hex_bare",
+    "hexa_bare",
+    "rgb",
+    "rgba",
+    "alpha",
+];
+
+fn apply_filter(color: SRGBA, filter: &str, amount: f32) -> anyhow::Result<SRGBA> {
+    let mut hsl = Hsl::from_color(color.color);
+    match filter {
+        "lighten" => hsl.lightness += amount,
+        "darken" => hsl.lightness -= amount,
+        "saturate" => hsl.saturation += amount,
+        "desaturate" => hsl.saturation -= amount,
+        "alpha" => {
+            let mut color = color;
+            color.alpha = amount.clamp(0.0, 1.0);
+            return Ok(color);
+        }
+        _ => bail!("unknown filter {}", filter),
+    };
+
+    let mut filtered = SRGBA(Srgba::from(Srgb::from_color(hsl.clamp())));
+    filtered.alpha = color.alpha;
+    Ok(filtered)
+}
+
+fn user_template_dir() -> PathBuf {
+    glib::user_config_dir()
+        .join("cosmic-theme-editor")
+        .join("templates")
+}
+
+/// Other apps include the rendered files, so they must not live in a cache directory
+fn user_template_output_dir() -> PathBuf {
+    glib::user_data_dir()
+        .join("cosmic-theme-editor")
+        .join("rendered")
+}
+
+/// Render every template in `~/.config/cosmic-theme-editor/templates` into
+/// `~/.local/share/cosmic-theme-editor/rendered`, keeping the template's file name.
+/// A light and dark pair is rendered into the `light` and `dark` subdirectories.
+/// All templates are rendered even if some of them fail.
+pub fn render_user_templates(
+    themes: &[(ThemeVariant, Theme<SRGBA>)],
+) -> anyhow::Result<Vec<PathBuf>> {
+    let template_dir = user_template_dir();
+    if !template_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut rendered = Vec::new();
+    let mut errors = Vec::new();
+    for (variant, theme) in themes {
+        let output_dir = match themes.len() {
+            1 => user_template_output_dir(),
+            _ => user_template_output_dir().join(variant.id()),
+        };
+        fs::create_dir_all(&output_dir)?;
+
+        let context = TemplateContext::new(theme);
+        for entry in fs::read_dir(&template_dir)? {
+            let path = entry?.path();
+            if !path.is_file() {
+                continue;
+            }
+            let output = output_dir.join(path.file_name().unwrap());
+            let result = fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|source| context.render(&source))
+                .and_then(|text| Ok(fs::write(&output, text)?));
+            match result {
+                Ok(()) => rendered.push(output),
+                Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(rendered)
+    } else {
+        bail!("Failed to render templates\n\n{}", errors.join("\n"))
+    }
+}
+
+pub fn format_color(color: &SRGBA, format: &str) -> anyhow::Result<String> {
+    let hexa = hex_from_rgba(color);
+    let [r, g, b, _]: [u8; 4] = Srgba::into_raw(color.into_format());
+    Ok(match format {
+        "hex" => hexa[..7].to_string(),
+        "hexa" => hexa,
+        "hex_bare" => hexa[1..7].to_string(),
+        "hexa_bare" => hexa[1..].to_string(),
+        "rgb" => format!("rgb({}, {}, {})", r, g, b),
+        "rgba" => css_rgba(color),
+        "alpha" => format!("{:.3}", color.alpha),
+        _ => bail!("unknown color format {}", format),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::rgba_from_hex;
+
+    fn context() -> TemplateContext {
+        TemplateContext {
+            name: "Test".into(),
+            colors: HashMap::from([
+                ("accent".into(), rgba_from_hex("#5294e2").unwrap()),
+                ("background".into(), rgba_from_hex("#000000").unwrap()),
+            ]),
+        }
+    }
+
+    #[test]
+    fn renders_every_placeholder() {
+        let rendered = context()
+            .render("{{name}}: {{accent}} {{ accent.rgb }} {{accent|hexa_bare}} {{ accent }}")
+            .unwrap();
+        assert_eq!(rendered, "Test: #5294e2 rgb(82, 148, 226) 5294e2ff #5294e2");
+    }
+
+    #[test]
+    fn filters() {
+        let context = context();
+        assert_eq!(
+            context.render("{{ accent | alpha(0.5) | rgba }}").unwrap(),
+            "rgba(82, 148, 226, 0.500)"
+        );
+        assert_eq!(
+            context.render("{{ background | lighten(1.0) }}").unwrap(),
+            "#ffffff"
+        );
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(
+            context().render(r"\{{ accent }} {{ accent }}").unwrap(),
+            "{{ accent }} #5294e2"
+        );
+    }
+
+    #[test]
+    fn missing_keys() {
+        let e = context().render("{{ missing }}").unwrap_err();
+        assert!(e.to_string().contains("missing"));
+        assert!(context().render("{{ accent.cmyk }}").is_err());
+    }
+
+    #[test]
+    fn malformed_placeholders() {
+        let context = context();
+        assert!(context.render("{{ accent").is_err());
+        assert!(context.render("{{ accent | blur(2) }}").is_err());
+        assert!(context.render("{{ accent | lighten(a lot) }}").is_err());
+        assert!(context.render("{{ accent | lighten(0.1 }}").is_err());
+    }
+}
@@ -0,0 +1,157 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Import from terminal color schemes
+
+use crate::util::{mix, rgba_from_hex, SRGBA};
+use anyhow::{anyhow, Context};
+use cosmic_theme::Selection;
+use serde::Deserialize;
+use std::{collections::HashMap, io::Cursor};
+
+const ANSI_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// The colors shared by terminal schemes
+#[derive(Debug, Default)]
+pub struct TerminalColors {
+    pub background: SRGBA,
+    pub foreground: SRGBA,
+    pub ansi: [Option<SRGBA>; 16],
+}
+
+impl TerminalColors {
+    /// Containers are stepped from the background towards the foreground,
+    /// the accent is blue and the destructive color is red.
+    pub fn into_selection(self) -> anyhow::Result<Selection<SRGBA>> {
+        let ansi = |i: usize| self.ansi[i].ok_or_else(|| anyhow!("missing color{}", i));
+
+        Ok(Selection {
+            background: self.background,
+            primary_container: mix(&self.background, &self.foreground, 0.08),
+            secondary_container: mix(&self.background, &self.foreground, 0.16),
+            accent: ansi(4)?,
+            destructive: ansi(1)?,
+            accent_text: self.ansi[12],
+            ..Default::default()
+        })
+    }
+}
+
+/// iTerm2 `.itermcolors` property list
+pub fn selection_from_iterm(plist: &[u8]) -> anyhow::Result<Selection<SRGBA>> {
+    let plist = plist::Value::from_reader(Cursor::new(plist))?;
+    let colors = plist
+        .as_dictionary()
+        .ok_or_else(|| anyhow!("not an iTerm2 color scheme"))?;
+    let color = |name: &str| -> anyhow::Result<SRGBA> {
+        let color = colors
+            .get(name)
+            .and_then(plist::Value::as_dictionary)
+            .ok_or_else(|| anyhow!("missing {}", name))?;
+        let component = |c: &str| {
+            color
+                .get(&format!("{} Component", c))
+                .and_then(plist::Value::as_real)
+                .map(|c| c as f32)
+        };
+        match (component("Red"), component("Green"), component("Blue")) {
+            (Some(r), Some(g), Some(b)) => Ok(SRGBA(palette::Srgba::new(
+                r,
+                g,
+                b,
+                component("Alpha").unwrap_or(1.0),
+            ))),
+            _ => Err(anyhow!("invalid {}", name)),
+        }
+    };
+
+    let mut terminal = TerminalColors {
+        background: color("Background Color")?,
+        foreground: color("Foreground Color")?,
+        ..Default::default()
+    };
+    for (i, c) in terminal.ansi.iter_mut().enumerate() {
+        *c = color(&format!("Ansi {} Color", i)).ok();
+    }
+    terminal.into_selection()
+}
+
+#[derive(Deserialize)]
+struct AlacrittyConfig {
+    colors: AlacrittyColors,
+}
+
+#[derive(Deserialize)]
+struct AlacrittyColors {
+    primary: AlacrittyPrimary,
+    #[serde(default)]
+    normal: HashMap<String, String>,
+    #[serde(default)]
+    bright: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct AlacrittyPrimary {
+    background: String,
+    foreground: String,
+}
+
+fn alacritty_color(color: &str) -> anyhow::Result<SRGBA> {
+    rgba_from_hex(color.trim().trim_start_matches("0x"))
+}
+
+impl AlacrittyConfig {
+    fn into_selection(self) -> anyhow::Result<Selection<SRGBA>> {
+        let colors = self.colors;
+        let mut terminal = TerminalColors {
+            background: alacritty_color(&colors.primary.background)?,
+            foreground: alacritty_color(&colors.primary.foreground)?,
+            ..Default::default()
+        };
+        for (i, name) in ANSI_NAMES.iter().enumerate() {
+            let normal = colors.normal.get(*name);
+            let bright = colors.bright.get(*name);
+            terminal.ansi[i] = normal.map(|c| alacritty_color(c)).transpose()?;
+            terminal.ansi[i + 8] = bright.map(|c| alacritty_color(c)).transpose()?;
+        }
+        terminal.into_selection()
+    }
+}
+
+/// Alacritty TOML config, or a color scheme from it
+pub fn selection_from_alacritty_toml(config: &str) -> anyhow::Result<Selection<SRGBA>> {
+    toml::from_str::<AlacrittyConfig>(config)?.into_selection()
+}
+
+/// Alacritty YAML config, used before Alacritty 0.13
+pub fn selection_from_alacritty_yaml(config: &str) -> anyhow::Result<Selection<SRGBA>> {
+    serde_yaml::from_str::<AlacrittyConfig>(config)?.into_selection()
+}
+
+/// Kitty `.conf` theme
+pub fn selection_from_kitty(conf: &str) -> anyhow::Result<Selection<SRGBA>> {
+    let colors: HashMap<&str, &str> = conf
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once(char::is_whitespace))
+        .map(|(key, value)| (key, value.trim()))
+        .collect();
+    let color = |name: &str| {
+        colors
+            .get(name)
+            .ok_or_else(|| anyhow!("missing {}", name))
+            .and_then(|c| rgba_from_hex(c).with_context(|| format!("invalid {}", name)))
+    };
+
+    let mut terminal = TerminalColors {
+        background: color("background")?,
+        foreground: color("foreground")?,
+        ..Default::default()
+    };
+    for (i, c) in terminal.ansi.iter_mut().enumerate() {
+        *c = color(&format!("color{}", i)).ok();
+    }
+    terminal.into_selection()
+}
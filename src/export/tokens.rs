@@ -0,0 +1,183 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! [W3C Design Tokens](https://design-tokens.github.io/community-group/format/) export and import
+
+use super::theme_colors;
+use crate::util::{hex_from_rgba, rgba_from_hex, SRGBA};
+use anyhow::{anyhow, bail};
+use cosmic_theme::{Selection, Theme};
+use serde_json::{json, Map, Value};
+
+const SELECTION_GROUP: &str = "selection";
+
+fn selection_colors(selection: &Selection<SRGBA>) -> Vec<(&'static str, SRGBA)> {
+    let mut colors = vec![
+        ("background", selection.background),
+        ("primary_container", selection.primary_container),
+        ("secondary_container", selection.secondary_container),
+        ("accent", selection.accent),
+        ("destructive", selection.destructive),
+    ];
+    if let Some(c) = selection.accent_text {
+        colors.push(("accent_text", c));
+    }
+    if let Some(c) = selection.accent_nav_handle_text {
+        colors.push(("accent_nav_handle_text", c));
+    }
+    colors
+}
+
+fn token(value: String) -> Value {
+    json!({ "$type": "color", "$value": value })
+}
+
+/// Export the selection and the derived theme as a design token file.
+/// Derived colors which are identical to a selected color are written as aliases of it.
+pub fn tokens_from_theme(theme: &Theme<SRGBA>, selection: &Selection<SRGBA>) -> String {
+    let selected = selection_colors(selection);
+    let mut root = Map::new();
+
+    root.insert(
+        SELECTION_GROUP.into(),
+        Value::Object(
+            selected
+                .iter()
+                .map(|(name, c)| (name.to_string(), token(hex_from_rgba(c))))
+                .collect(),
+        ),
+    );
+
+    for (path, color) in theme_colors(theme) {
+        let value = match selected.iter().find(|(_, c)| *c == color) {
+            Some((name, _)) => format!("{{{}.{}}}", SELECTION_GROUP, name),
+            None => hex_from_rgba(&color),
+        };
+
+        let (leaf, groups) = path.split_last().unwrap();
+        let mut group = &mut root;
+        for name in groups {
+            group = group
+                .entry(*name)
+                .or_insert_with(|| Value::Object(Map::new()))
+                .as_object_mut()
+                .unwrap();
+        }
+        group.insert(leaf.to_string(), token(value));
+    }
+
+    serde_json::to_string_pretty(&Value::Object(root)).unwrap()
+}
+
+/// Import a selection from a design token file.
+/// Files without a `selection` group fall back to the surface colors of the derived theme.
+pub fn selection_from_tokens(tokens: &str) -> anyhow::Result<Selection<SRGBA>> {
+    let root: Value = serde_json::from_str(tokens)?;
+    let color = |path: &str| resolve(&root, path, 0);
+
+    Ok(if root.get(SELECTION_GROUP).is_some() {
+        Selection {
+            background: color("selection.background")?,
+            primary_container: color("selection.primary_container")?,
+            secondary_container: color("selection.secondary_container")?,
+            accent: color("selection.accent")?,
+            destructive: color("selection.destructive")?,
+            accent_text: color("selection.accent_text").ok(),
+            accent_nav_handle_text: color("selection.accent_nav_handle_text").ok(),
+        }
+    } else {
+        Selection {
+            background: color("background.container")?,
+            primary_container: color("primary.container")?,
+            secondary_container: color("secondary.container")?,
+            accent: color("accent.accent")?,
+            destructive: color("destructive.destructive.default")?,
+            accent_text: color("accent.accent_text").ok(),
+            accent_nav_handle_text: color("accent.accent_nav_handle_text").ok(),
+        }
+    })
+}
+
+fn resolve(root: &Value, path: &str, depth: usize) -> anyhow::Result<SRGBA> {
+    if depth > 16 {
+        bail!("alias loop while resolving token {}", path);
+    }
+
+    let token = path
+        .split('.')
+        .try_fold(root, |group, name| group.get(name))
+        .ok_or_else(|| anyhow!("missing token {}", path))?;
+    let value = token
+        .get("$value")
+        .or_else(|| token.get("value"))
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("token {} is not a color", path))?;
+
+    match value.strip_prefix('{').and_then(|v| v.strip_suffix('}')) {
+        Some(alias) => resolve(root, alias, depth + 1),
+        None => rgba_from_hex(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::test_selection;
+    use cosmic_theme::{ColorPicker, Exact, ThemeConstraints};
+
+    fn assert_same_selection(a: &Selection<SRGBA>, b: &Selection<SRGBA>) {
+        assert_eq!(a.background, b.background);
+        assert_eq!(a.primary_container, b.primary_container);
+        assert_eq!(a.secondary_container, b.secondary_container);
+        assert_eq!(a.accent, b.accent);
+        assert_eq!(a.accent_text, b.accent_text);
+        assert_eq!(a.accent_nav_handle_text, b.accent_nav_handle_text);
+        assert_eq!(a.destructive, b.destructive);
+    }
+
+    #[test]
+    fn round_trip() {
+        let selection = test_selection();
+        let theme = Exact::new(selection, ThemeConstraints::default())
+            .theme_derivation()
+            .derived;
+        let tokens = tokens_from_theme(&theme, &selection);
+        assert_same_selection(&selection_from_tokens(&tokens).unwrap(), &selection);
+    }
+
+    #[test]
+    fn aliases_resolve_without_selection_group() {
+        let selection = test_selection();
+        let theme = Exact::new(selection, ThemeConstraints::default())
+            .theme_derivation()
+            .derived;
+        let mut root: Value = serde_json::from_str(&tokens_from_theme(&theme, &selection)).unwrap();
+        // derived colors alias the selection, so keep it but hide it from the importer
+        let group = root
+            .as_object_mut()
+            .unwrap()
+            .remove(SELECTION_GROUP)
+            .unwrap();
+        root.as_object_mut()
+            .unwrap()
+            .insert("palette".into(), group);
+        let tokens = serde_json::to_string(&root)
+            .unwrap()
+            .replace("{selection.", "{palette.");
+
+        let imported = selection_from_tokens(&tokens).unwrap();
+        assert_eq!(imported.background, theme.background.container);
+        assert_eq!(imported.accent, theme.accent.accent);
+        assert_eq!(imported.destructive, theme.destructive.destructive.default);
+    }
+
+    #[test]
+    fn alias_loop_is_an_error() {
+        let tokens = r#"{
+            "selection": {
+                "background": { "$type": "color", "$value": "{selection.accent}" },
+                "accent": { "$type": "color", "$value": "{selection.background}" }
+            }
+        }"#;
+        assert!(selection_from_tokens(tokens).is_err());
+    }
+}
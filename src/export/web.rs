@@ -0,0 +1,50 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use super::theme_colors;
+use crate::util::{hex_from_rgba, SRGBA};
+use cosmic_theme::Theme;
+use std::fmt::Write;
+
+fn variable_name(path: &[&str]) -> String {
+    format!("cosmic-{}", path.join("-").replace('_', "-"))
+}
+
+/// `:root` custom properties, e.g. `--cosmic-background-container`
+pub fn css_custom_properties(theme: &Theme<SRGBA>) -> String {
+    let mut css = String::from(":root {\n");
+    for (path, color) in theme_colors(theme) {
+        let _ = writeln!(
+            css,
+            "  --{}: {};",
+            variable_name(&path),
+            hex_from_rgba(&color)
+        );
+    }
+    css.push_str("}\n");
+    css
+}
+
+/// SCSS variables, e.g. `$cosmic-background-container`
+pub fn scss_variables(theme: &Theme<SRGBA>) -> String {
+    variables(theme, '$')
+}
+
+/// LESS variables, e.g. `@cosmic-background-container`
+pub fn less_variables(theme: &Theme<SRGBA>) -> String {
+    variables(theme, '@')
+}
+
+fn variables(theme: &Theme<SRGBA>, sigil: char) -> String {
+    theme_colors(theme)
+        .into_iter()
+        .fold(String::new(), |mut vars, (path, color)| {
+            let _ = writeln!(
+                vars,
+                "{}{}: {};",
+                sigil,
+                variable_name(&path),
+                hex_from_rgba(&color)
+            );
+            vars
+        })
+}
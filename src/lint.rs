@@ -0,0 +1,308 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Accessibility and sanity checks for derived themes
+
+use crate::util::{contrast_ratio, over, SRGBA};
+use cosmic_theme::{Container, Selection, Theme};
+use palette::{ColorDifference, IntoColor, Lab};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rule {
+    /// WCAG contrast of text on the surface it is drawn on
+    Contrast,
+    /// The accent and destructive colors can be told apart
+    Distinguishable,
+    /// Surfaces are opaque
+    SurfaceAlpha,
+    /// Optional selection colors are set instead of left to the derivation
+    MissingOptional,
+}
+
+impl Rule {
+    pub const ALL: [Self; 4] = [
+        Self::Contrast,
+        Self::Distinguishable,
+        Self::SurfaceAlpha,
+        Self::MissingOptional,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Contrast => "contrast",
+            Self::Distinguishable => "distinguishable",
+            Self::SurfaceAlpha => "surface-alpha",
+            Self::MissingOptional => "missing-optional",
+        }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|rule| rule.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|rule| rule.name()).collect();
+                anyhow::anyhow!("unknown rule {}, expected one of {}", s, names.join(", "))
+            })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Something the linter could not check
+    Note,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Violation {
+    pub rule: Rule,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Note => "note",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}[{}]: {}", severity, self.rule.name(), self.message)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct LintConfig {
+    /// WCAG contrast ratio text needs on its surface
+    pub min_contrast: f32,
+    /// CIEDE2000 difference needed between the accent and destructive colors
+    pub min_accent_distance: f32,
+    pub disabled: Vec<Rule>,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            min_contrast: 4.5,
+            min_accent_distance: 15.0,
+            disabled: Vec::new(),
+        }
+    }
+}
+
+/// Check a theme, and the selection it was derived from when it is known
+pub fn lint(
+    theme: &Theme<SRGBA>,
+    selection: Option<&Selection<SRGBA>>,
+    config: &LintConfig,
+) -> Vec<Violation> {
+    let mut linter = Linter {
+        config,
+        violations: Vec::new(),
+    };
+    let base = theme.background.container;
+
+    for (name, container) in [
+        ("background", &theme.background),
+        ("primary", &theme.primary),
+        ("secondary", &theme.secondary),
+    ] {
+        linter.container(name, container, &base);
+        let surface = over(&container.container, &base);
+        linter.contrast(
+            "accent.accent_text",
+            &theme.accent.accent_text,
+            &format!("{}.container", name),
+            &surface,
+        );
+    }
+
+    let suggested = &theme.accent.suggested;
+    linter.contrast(
+        "accent.suggested.text",
+        &suggested.text,
+        "accent.suggested.default",
+        &over(&suggested.default, &base),
+    );
+    linter.contrast(
+        "accent.accent_nav_handle_text",
+        &theme.accent.accent_nav_handle_text,
+        "accent.accent",
+        &over(&theme.accent.accent, &base),
+    );
+    let destructive = &theme.destructive.destructive;
+    linter.contrast(
+        "destructive.destructive.text",
+        &destructive.text,
+        "destructive.destructive.default",
+        &over(&destructive.default, &base),
+    );
+
+    if linter.enabled(Rule::Distinguishable) {
+        let accent_lab: Lab = theme.accent.accent.color.into_color();
+        let destructive_lab: Lab = destructive.default.color.into_color();
+        let distance = accent_lab.get_color_difference(&destructive_lab);
+        if distance < config.min_accent_distance {
+            linter.violations.push(Violation {
+                rule: Rule::Distinguishable,
+                severity: Severity::Error,
+                message: format!(
+                    "accent {} and destructive {} are too similar: difference {:.1} < {:.1}",
+                    theme.accent.accent, destructive.default, distance, config.min_accent_distance
+                ),
+            });
+        }
+    }
+
+    match (selection, linter.enabled(Rule::MissingOptional)) {
+        (Some(selection), true) => {
+            for (name, color) in [
+                ("accent_text", selection.accent_text),
+                ("accent_nav_handle_text", selection.accent_nav_handle_text),
+            ] {
+                if color.is_none() {
+                    linter.violations.push(Violation {
+                        rule: Rule::MissingOptional,
+                        severity: Severity::Warning,
+                        message: format!("{} is not set, so it is derived", name),
+                    });
+                }
+            }
+        }
+        // a derived theme has every color set, only its selection tells what was left out
+        (None, true) => linter.violations.push(Violation {
+            rule: Rule::MissingOptional,
+            severity: Severity::Note,
+            message: "skipped, the theme has no selection to check".to_string(),
+        }),
+        (_, false) => {}
+    }
+
+    linter.violations
+}
+
+struct Linter<'a> {
+    config: &'a LintConfig,
+    violations: Vec<Violation>,
+}
+
+impl Linter<'_> {
+    fn enabled(&self, rule: Rule) -> bool {
+        !self.config.disabled.contains(&rule)
+    }
+
+    fn container(&mut self, name: &str, container: &Container<SRGBA>, base: &SRGBA) {
+        if self.enabled(Rule::SurfaceAlpha) && container.container.alpha < 1.0 {
+            self.violations.push(Violation {
+                rule: Rule::SurfaceAlpha,
+                severity: Severity::Warning,
+                message: format!(
+                    "{}.container {} is translucent, alpha {:.2}",
+                    name, container.container, container.container.alpha
+                ),
+            });
+        }
+
+        let surface = over(&container.container, base);
+        for (text, color) in [
+            ("container_text", &container.container_text),
+            (
+                "container_text_opacity_80",
+                &container.container_text_opacity_80,
+            ),
+        ] {
+            self.contrast(
+                &format!("{}.{}", name, text),
+                color,
+                &format!("{}.container", name),
+                &surface,
+            );
+        }
+
+        let component = &container.container_component;
+        for (state, color) in [
+            ("default", &component.default),
+            ("hover", &component.hover),
+            ("pressed", &component.pressed),
+            ("focused", &component.focused),
+        ] {
+            self.contrast(
+                &format!("{}.container_component.text", name),
+                &component.text,
+                &format!("{}.container_component.{}", name, state),
+                &over(color, &surface),
+            );
+        }
+    }
+
+    /// Text is composited over its surface, as it would be drawn
+    fn contrast(&mut self, text_name: &str, text: &SRGBA, surface_name: &str, surface: &SRGBA) {
+        if !self.enabled(Rule::Contrast) {
+            return;
+        }
+        let ratio = contrast_ratio(&over(text, surface), surface);
+        if ratio < self.config.min_contrast {
+            self.violations.push(Violation {
+                rule: Rule::Contrast,
+                severity: Severity::Error,
+                message: format!(
+                    "{} {} on {} {}: contrast {:.2} < {:.2}",
+                    text_name, text, surface_name, surface, ratio, self.config.min_contrast
+                ),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::test_selection;
+    use cosmic_theme::{ColorPicker, Exact, ThemeConstraints};
+
+    fn derived() -> Theme<SRGBA> {
+        Exact::new(test_selection(), ThemeConstraints::default())
+            .theme_derivation()
+            .derived
+    }
+
+    #[test]
+    fn nav_handle_text_on_accent() {
+        let mut theme = derived();
+        theme.accent.accent_nav_handle_text = theme.accent.accent;
+        let violations = lint(&theme, None, &LintConfig::default());
+        assert!(violations
+            .iter()
+            .any(|v| v.rule == Rule::Contrast
+                && v.message.starts_with("accent.accent_nav_handle_text")));
+    }
+
+    #[test]
+    fn missing_optional_without_selection_is_skipped() {
+        let theme = derived();
+        let violations = lint(&theme, None, &LintConfig::default());
+        let skipped: Vec<_> = violations
+            .iter()
+            .filter(|v| v.rule == Rule::MissingOptional)
+            .collect();
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].severity, Severity::Note);
+
+        let mut selection = test_selection();
+        selection.accent_text = None;
+        let violations = lint(&theme, Some(&selection), &LintConfig::default());
+        assert!(violations.iter().any(|v| v.rule == Rule::MissingOptional
+            && v.severity == Severity::Warning
+            && v.message.starts_with("accent_text")));
+    }
+}
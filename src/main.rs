@@ -1,6 +1,13 @@
 mod application;
+mod cli;
 mod components;
 mod config;
+mod dbus;
+mod export;
+mod lint;
+mod metadata;
+mod project;
+mod syntax;
 mod util;
 mod window;
 
@@ -16,6 +23,11 @@ fn main() {
     // Initialize logger
     pretty_env_logger::init();
 
+    // Headless subcommands run without GTK
+    if let Some(code) = cli::run() {
+        std::process::exit(code);
+    }
+
     // Prepare i18n
     gettextrs::setlocale(LocaleCategory::LcAll, "");
     gettextrs::bindtextdomain(GETTEXT_PACKAGE, LOCALEDIR).expect("Unable to bind the text domain");
@@ -0,0 +1,111 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Attribution and descriptive details of a theme, kept next to the theme itself
+
+use crate::util::{is_dark, now_iso8601, SRGBA};
+use cosmic_theme::Theme;
+use gettextrs::gettext;
+use gtk4::glib;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemeVariant {
+    Light,
+    #[default]
+    Dark,
+}
+
+impl ThemeVariant {
+    pub const ALL: [Self; 2] = [Self::Light, Self::Dark];
+
+    /// The variant a theme looks like, from the lightness of its background
+    pub fn of(theme: &Theme<SRGBA>) -> Self {
+        if is_dark(&theme.background.container) {
+            Self::Dark
+        } else {
+            Self::Light
+        }
+    }
+
+    pub fn other(self) -> Self {
+        match self {
+            Self::Light => Self::Dark,
+            Self::Dark => Self::Light,
+        }
+    }
+
+    /// Untranslated name, used in the names of saved themes
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::Light => "light",
+            Self::Dark => "dark",
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            Self::Light => gettext("Light"),
+            Self::Dark => gettext("Dark"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeMetadata {
+    pub author: String,
+    pub description: String,
+    /// SPDX license identifier, such as `CC-BY-SA-4.0`
+    pub license: String,
+    pub tags: Vec<String>,
+    pub variant: ThemeVariant,
+    /// ISO 8601 time the theme was first saved
+    pub created: String,
+    /// ISO 8601 time the theme was last saved
+    pub modified: String,
+}
+
+impl ThemeMetadata {
+    fn path(theme_name: &str) -> PathBuf {
+        glib::user_data_dir()
+            .join("cosmic-theme-editor")
+            .join("metadata")
+            .join(format!("{}.ron", theme_name.replace('/', "_")))
+    }
+
+    /// Metadata saved with the named theme, if there is any
+    pub fn load(theme_name: &str) -> anyhow::Result<Option<Self>> {
+        let path = Self::path(theme_name);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(ron::from_str(&fs::read_to_string(path)?)?))
+    }
+
+    /// Save next to the named theme, updating the timestamps
+    pub fn save(&mut self, theme_name: &str) -> anyhow::Result<()> {
+        self.modified = now_iso8601();
+        if self.created.is_empty() {
+            self.created = self.modified.clone();
+        }
+
+        let path = Self::path(theme_name);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, ron::ser::to_string_pretty(self, PrettyConfig::new())?)?;
+        Ok(())
+    }
+
+    /// Tags from comma separated text
+    pub fn set_tags(&mut self, tags: &str) {
+        self.tags = tags
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect();
+    }
+}
@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Theme projects pair a light and a dark variant that share their accent and destructive colors
+
+use crate::{metadata::ThemeVariant, util::SRGBA};
+use cosmic_theme::{Selection, ThemeConstraints};
+use palette::{IntoColor, Lch, Srgba};
+use serde::{Deserialize, Serialize};
+
+/// The colors and constraints one variant is derived from
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct VariantSelection {
+    pub selection: Selection<SRGBA>,
+    pub constraints: ThemeConstraints,
+}
+
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ThemeProject {
+    pub light: Option<VariantSelection>,
+    pub dark: Option<VariantSelection>,
+}
+
+impl ThemeProject {
+    pub fn get(&self, variant: ThemeVariant) -> Option<VariantSelection> {
+        match variant {
+            ThemeVariant::Light => self.light,
+            ThemeVariant::Dark => self.dark,
+        }
+    }
+
+    fn get_mut(&mut self, variant: ThemeVariant) -> &mut Option<VariantSelection> {
+        match variant {
+            ThemeVariant::Light => &mut self.light,
+            ThemeVariant::Dark => &mut self.dark,
+        }
+    }
+
+    /// Set a variant, and share its accent and destructive colors with the other one
+    pub fn set(&mut self, variant: ThemeVariant, v: VariantSelection) {
+        *self.get_mut(variant) = Some(v);
+        if let Some(other) = self.get_mut(variant.other()) {
+            other.selection.accent = v.selection.accent;
+            other.selection.destructive = v.selection.destructive;
+        }
+    }
+
+    /// Get a variant, generating it from the other one if it was never set
+    pub fn get_or_generate(&mut self, variant: ThemeVariant) -> VariantSelection {
+        match self.get(variant) {
+            Some(v) => v,
+            None => self.generate(variant.other()),
+        }
+    }
+
+    /// Replace the other variant with one generated from `from`
+    pub fn generate(&mut self, from: ThemeVariant) -> VariantSelection {
+        let source = self.get(from).unwrap_or_default();
+        let generated = VariantSelection {
+            selection: invert_surfaces(&source.selection),
+            constraints: source.constraints,
+        };
+        *self.get_mut(from.other()) = Some(generated);
+        generated
+    }
+
+    /// The variants that were created, with the name each derived theme is saved under.
+    /// A single variant is saved under the project name, like `derive --write` does,
+    /// a pair is saved as `<name>-light` and `<name>-dark`.
+    pub fn theme_names(&self, name: &str) -> Vec<(ThemeVariant, VariantSelection, String)> {
+        let created: Vec<_> = ThemeVariant::ALL
+            .into_iter()
+            .filter_map(|variant| self.get(variant).map(|v| (variant, v)))
+            .collect();
+        let paired = created.len() > 1;
+        created
+            .into_iter()
+            .map(|(variant, v)| {
+                let theme_name = if paired {
+                    format!("{}-{}", name, variant.id())
+                } else {
+                    name.to_string()
+                };
+                (variant, v, theme_name)
+            })
+            .collect()
+    }
+}
+
+/// The project name and variant of a theme saved as one of a pair, such as `Foo` and
+/// [`ThemeVariant::Dark`] for `Foo-dark`.
+/// A single variant is saved under the project name, so the suffix is only a hint.
+pub fn split_variant(theme_name: &str) -> Option<(&str, ThemeVariant)> {
+    ThemeVariant::ALL.into_iter().find_map(|variant| {
+        theme_name
+            .strip_suffix(&format!("-{}", variant.id()))
+            .map(|name| (name, variant))
+    })
+}
+
+/// Flip the lightness of the surfaces, keeping their hue and chroma.
+/// The accent, destructive and text colors are kept as they are.
+pub fn invert_surfaces(selection: &Selection<SRGBA>) -> Selection<SRGBA> {
+    let invert = |c: SRGBA| {
+        let mut lch: Lch = c.color.into_color();
+        lch.l = 100.0 - lch.l;
+        let rgb: palette::Srgb = lch.into_color();
+        SRGBA(Srgba::from_components((
+            rgb.red.clamp(0.0, 1.0),
+            rgb.green.clamp(0.0, 1.0),
+            rgb.blue.clamp(0.0, 1.0),
+            c.alpha,
+        )))
+    };
+
+    let mut inverted = *selection;
+    inverted.background = invert(selection.background);
+    inverted.primary_container = invert(selection.primary_container);
+    inverted.secondary_container = invert(selection.secondary_container);
+    inverted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_variant_keeps_the_project_name() {
+        let project = ThemeProject {
+            dark: Some(VariantSelection::default()),
+            ..Default::default()
+        };
+        let names: Vec<_> = project
+            .theme_names("Foo")
+            .into_iter()
+            .map(|(variant, _, name)| (variant, name))
+            .collect();
+        assert_eq!(names, vec![(ThemeVariant::Dark, "Foo".to_string())]);
+    }
+
+    #[test]
+    fn pair_is_named_by_variant() {
+        let mut project = ThemeProject {
+            light: Some(VariantSelection::default()),
+            ..Default::default()
+        };
+        project.generate(ThemeVariant::Light);
+        let names: Vec<_> = project
+            .theme_names("Foo")
+            .into_iter()
+            .map(|(_, _, name)| name)
+            .collect();
+        assert_eq!(names, vec!["Foo-light", "Foo-dark"]);
+        for (name, variant) in names.iter().zip(ThemeVariant::ALL) {
+            assert_eq!(split_variant(name), Some(("Foo", variant)));
+        }
+        assert_eq!(split_variant("Foo"), None);
+    }
+}
@@ -0,0 +1,132 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use crate::util::{contrast_ratio, is_dark, SRGBA};
+use cosmic_theme::Theme;
+use gettextrs::gettext;
+use palette::{Clamp, FromColor, Hue, IntoColor, Lch, Srgb, Srgba};
+use serde::{Deserialize, Serialize};
+
+/// minimum contrast of highlighted code against the background
+const TEXT_CONTRAST: f32 = 4.5;
+/// comments are allowed to recede a little
+const COMMENT_CONTRAST: f32 = 3.0;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SyntaxRole {
+    Keyword,
+    Function,
+    Type,
+    String,
+    Number,
+    Comment,
+    Error,
+}
+
+impl SyntaxRole {
+    pub const ALL: [SyntaxRole; 7] = [
+        SyntaxRole::Keyword,
+        SyntaxRole::Function,
+        SyntaxRole::Type,
+        SyntaxRole::String,
+        SyntaxRole::Number,
+        SyntaxRole::Comment,
+        SyntaxRole::Error,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SyntaxRole::Keyword => "keyword",
+            SyntaxRole::Function => "function",
+            SyntaxRole::Type => "type",
+            SyntaxRole::String => "string",
+            SyntaxRole::Number => "number",
+            SyntaxRole::Comment => "comment",
+            SyntaxRole::Error => "error",
+        }
+    }
+
+    /// The role as shown next to its color button
+    pub fn label(self) -> String {
+        match self {
+            SyntaxRole::Keyword => gettext("Keyword"),
+            SyntaxRole::Function => gettext("Function"),
+            SyntaxRole::Type => gettext("Type"),
+            SyntaxRole::String => gettext("String"),
+            SyntaxRole::Number => gettext("Number"),
+            SyntaxRole::Comment => gettext("Comment"),
+            SyntaxRole::Error => gettext("Error"),
+        }
+    }
+}
+
+/// Colors for syntax highlighting, used by the editor exporters
+#[derive(Copy, Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct SyntaxPalette {
+    pub keyword: SRGBA,
+    pub function: SRGBA,
+    #[serde(rename = "type")]
+    pub type_: SRGBA,
+    pub string: SRGBA,
+    pub number: SRGBA,
+    pub comment: SRGBA,
+    pub error: SRGBA,
+}
+
+impl SyntaxPalette {
+    /// Space hues evenly around the accent, and tune their lightness for contrast against
+    /// the background. The error color keeps the hue of the destructive color.
+    pub fn derive(theme: &Theme<SRGBA>) -> Self {
+        let bg = theme.background.container;
+        let accent: Lch = theme.accent.accent.color.into_color();
+        let destructive: Lch = theme.destructive.destructive.default.color.into_color();
+        let spaced = |step: f32| tune(accent.shift_hue(step * 72.0), &bg, TEXT_CONTRAST);
+        let comment = Lch::new(accent.l, accent.chroma.min(8.0), accent.hue);
+
+        Self {
+            keyword: spaced(0.0),
+            function: spaced(1.0),
+            type_: spaced(2.0),
+            string: spaced(3.0),
+            number: spaced(4.0),
+            comment: tune(comment, &bg, COMMENT_CONTRAST),
+            error: tune(destructive, &bg, TEXT_CONTRAST),
+        }
+    }
+
+    pub fn get(&self, role: SyntaxRole) -> SRGBA {
+        match role {
+            SyntaxRole::Keyword => self.keyword,
+            SyntaxRole::Function => self.function,
+            SyntaxRole::Type => self.type_,
+            SyntaxRole::String => self.string,
+            SyntaxRole::Number => self.number,
+            SyntaxRole::Comment => self.comment,
+            SyntaxRole::Error => self.error,
+        }
+    }
+
+    pub fn set(&mut self, role: SyntaxRole, color: SRGBA) {
+        match role {
+            SyntaxRole::Keyword => self.keyword = color,
+            SyntaxRole::Function => self.function = color,
+            SyntaxRole::Type => self.type_ = color,
+            SyntaxRole::String => self.string = color,
+            SyntaxRole::Number => self.number = color,
+            SyntaxRole::Comment => self.comment = color,
+            SyntaxRole::Error => self.error = color,
+        }
+    }
+}
+
+/// Move the lightness of `color` away from `bg` until it reaches the `target` contrast ratio
+fn tune(mut color: Lch, bg: &SRGBA, target: f32) -> SRGBA {
+    let step = if is_dark(bg) { 2.0 } else { -2.0 };
+
+    loop {
+        let rgba = SRGBA(Srgba::from(Srgb::from_color(color).clamp()));
+        if contrast_ratio(&rgba, bg) >= target || !(0.0..=100.0).contains(&(color.l + step)) {
+            return rgba;
+        }
+        color.l += step;
+    }
+}
@@ -1,13 +1,17 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 use core::fmt;
-use std::ops::{Deref, DerefMut};
+use std::{
+    ops::{Deref, DerefMut},
+    path::Path,
+};
 
 use cosmic_theme::Hex;
 use gtk4::{
     gdk::RGBA,
     gdk_pixbuf::{Colorspace, Pixbuf},
     gio::File,
+    glib,
     prelude::*,
 };
 use hex::encode;
@@ -18,6 +22,77 @@ pub fn hex_from_rgba(rgba: &Srgba) -> String {
     let hex = encode::<[u8; 4]>(Srgba::into_raw(rgba.into_format()));
     format!("#{hex}")
 }
+
+pub fn rgba_from_hex(hex: &str) -> anyhow::Result<SRGBA> {
+    let digits = hex.trim().trim_start_matches('#');
+    let digits: String = match digits.len() {
+        3 | 4 => digits.chars().flat_map(|c| [c, c]).collect(),
+        _ => digits.to_string(),
+    };
+    let mut rgba = [u8::MAX; 4];
+    match digits.len() {
+        6 | 8 => hex::decode_to_slice(&digits, &mut rgba[..digits.len() / 2])?,
+        _ => anyhow::bail!("invalid hex color: {}", hex),
+    };
+    let [r, g, b, a] = rgba;
+    Ok(SRGBA(Srgba::<u8>::new(r, g, b, a).into_format()))
+}
+
+/// The current UTC time in ISO 8601 format
+pub fn now_iso8601() -> String {
+    glib::DateTime::now_utc()
+        .and_then(|now| now.format_iso8601())
+        .map(|now| now.to_string())
+        .unwrap_or_default()
+}
+
+pub fn css_rgba(rgba: &Srgba) -> String {
+    let [r, g, b, _]: [u8; 4] = Srgba::into_raw(rgba.into_format());
+    format!("rgba({}, {}, {}, {:.3})", r, g, b, rgba.alpha)
+}
+/// WCAG 2 relative luminance
+pub fn relative_luminance(rgba: &Srgba) -> f32 {
+    let lin = rgba.color.into_linear();
+    0.2126 * lin.red + 0.7152 * lin.green + 0.0722 * lin.blue
+}
+
+/// Luminance above which dark text has more contrast than light text
+const MID_LUMINANCE: f32 = 0.18;
+
+/// Whether light text reads better than dark text on `surface`.
+/// This decides if a theme is light or dark everywhere in the editor.
+pub fn is_dark(surface: &Srgba) -> bool {
+    relative_luminance(surface) < MID_LUMINANCE
+}
+
+/// WCAG 2 contrast ratio, from 1 to 21
+pub fn contrast_ratio(a: &Srgba, b: &Srgba) -> f32 {
+    let (a, b) = (relative_luminance(a), relative_luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// Composite a possibly translucent color over an opaque one
+pub fn over(fg: &Srgba, bg: &Srgba) -> SRGBA {
+    let a = fg.alpha;
+    SRGBA(Srgba::new(
+        fg.red * a + bg.red * (1.0 - a),
+        fg.green * a + bg.green * (1.0 - a),
+        fg.blue * a + bg.blue * (1.0 - a),
+        1.0,
+    ))
+}
+
+/// Interpolate from `a` to `b`
+pub fn mix(a: &Srgba, b: &Srgba, t: f32) -> SRGBA {
+    let lerp = |a: f32, b: f32| a + (b - a) * t;
+    SRGBA(Srgba::new(
+        lerp(a.red, b.red),
+        lerp(a.green, b.green),
+        lerp(a.blue, b.blue),
+        lerp(a.alpha, b.alpha),
+    ))
+}
+
 use kmeans_colors::{get_kmeans_hamerly, Kmeans, Sort};
 use palette::{IntoColor, Lab, Srgb};
 
@@ -78,41 +153,98 @@ impl fmt::Display for SRGBA {
 }
 
 pub fn palette_from_image(f: File) -> Option<Vec<SRGBA>> {
-    // calculate kmeans colors from file
-    if let Some(Ok(img)) = f.path().map(|p| Pixbuf::from_file(p)) {
-        if img.bits_per_sample() == 8 && img.colorspace() == Colorspace::Rgb {
-            let pixels = unsafe { img.pixels() };
-            let lab: Vec<Lab> = if img.has_alpha() {
-                Srgba::from_raw_slice(pixels)
-                    .iter()
-                    .map(|x| x.color.into_format().into_color())
-                    .collect()
-            } else {
-                Srgb::from_raw_slice(pixels)
-                    .iter()
-                    .map(|x| x.into_format().into_color())
-                    .collect()
-            };
-
-            let mut result = Kmeans::new();
-
-            // TODO random seed
-            for i in 0..2 {
-                let run_result = get_kmeans_hamerly(5, 20, 5.0, false, &lab, i as u64);
-                if run_result.score < result.score {
-                    result = run_result;
-                }
-            }
-            let mut res = Lab::sort_indexed_colors(&result.centroids, &result.indices);
-            res.sort_unstable_by(|a, b| (b.percentage).partial_cmp(&a.percentage).unwrap());
-            let colors: Vec<SRGBA> = res.iter().map(|x| SRGBA(x.centroid.into_color())).collect();
-            Some(colors)
-        } else {
-            eprintln!("unsupported color format");
+    let path = f.path()?;
+    match palette_from_path(&path, 5, 0) {
+        Ok(palette) => Some(palette.into_iter().map(|(c, _)| c).collect()),
+        Err(e) => {
             // TODO error dialog msg
+            eprintln!("{}", e);
             None
         }
+    }
+}
+
+/// The dominant colors of an image, with the fraction of the image each covers, most common first
+pub fn palette_from_path(
+    path: &Path,
+    clusters: usize,
+    seed: u64,
+) -> anyhow::Result<Vec<(SRGBA, f32)>> {
+    // calculate kmeans colors from file
+    let img = Pixbuf::from_file(path)?;
+    if img.bits_per_sample() != 8 || img.colorspace() != Colorspace::Rgb {
+        anyhow::bail!("unsupported color format");
+    }
+
+    let pixels = unsafe { img.pixels() };
+    let lab: Vec<Lab> = if img.has_alpha() {
+        Srgba::from_raw_slice(pixels)
+            .iter()
+            .map(|x| x.color.into_format().into_color())
+            .collect()
     } else {
-        None
+        Srgb::from_raw_slice(pixels)
+            .iter()
+            .map(|x| x.into_format().into_color())
+            .collect()
+    };
+
+    let mut result = Kmeans::new();
+    for i in 0..2 {
+        let run_result = get_kmeans_hamerly(clusters, 20, 5.0, false, &lab, seed.wrapping_add(i));
+        if run_result.score < result.score {
+            result = run_result;
+        }
+    }
+    let mut res = Lab::sort_indexed_colors(&result.centroids, &result.indices);
+    res.sort_unstable_by(|a, b| (b.percentage).partial_cmp(&a.percentage).unwrap());
+    Ok(res
+        .iter()
+        .map(|x| (SRGBA(x.centroid.into_color()), x.percentage))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_short_forms() {
+        assert_eq!(
+            rgba_from_hex("#fa0").unwrap(),
+            rgba_from_hex("#ffaa00ff").unwrap()
+        );
+        assert_eq!(
+            rgba_from_hex("#fa08").unwrap(),
+            rgba_from_hex("#ffaa0088").unwrap()
+        );
+    }
+
+    #[test]
+    fn hex_with_alpha() {
+        let c = rgba_from_hex("#10203040").unwrap();
+        let [r, g, b, a]: [u8; 4] = Srgba::into_raw(c.into_format());
+        assert_eq!([r, g, b, a], [0x10, 0x20, 0x30, 0x40]);
+        assert_eq!(hex_from_rgba(&c), "#10203040");
+    }
+
+    #[test]
+    fn hex_without_alpha_is_opaque() {
+        assert_eq!(rgba_from_hex(" 5294e2 ").unwrap().alpha, 1.0);
+    }
+
+    #[test]
+    fn invalid_hex() {
+        for hex in [
+            "",
+            "#",
+            "#12",
+            "#12345",
+            "#1234567",
+            "#123456789",
+            "#gggggg",
+        ] {
+            assert!(rgba_from_hex(hex).is_err(), "{:?} should not parse", hex);
+        }
     }
 }
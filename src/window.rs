@@ -67,4 +67,16 @@ impl ThemeEditorApplicationWindow {
         imp.theme_editor.set(Some(theme_editor));
         self_
     }
+
+    pub fn theme_editor(&self) -> Option<ThemeEditor> {
+        self.child()
+            .and_then(|child| child.downcast::<ThemeEditor>().ok())
+    }
+
+    /// Open a theme or image in the editor
+    pub fn open_file(&self, f: &gio::File) {
+        if let Some(theme_editor) = self.theme_editor() {
+            theme_editor.open_file(f);
+        }
+    }
 }